@@ -0,0 +1,141 @@
+use super::quota::{self, QuotaState};
+use crate::conf::auth::{ApiKey, Scope};
+use crate::CONFIG;
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome},
+    Request,
+};
+use std::marker::PhantomData;
+use tracing::warn;
+
+// 每个请求只解析一次 API Key，结果缓存在 Request 的 local cache 中，供守卫与访问日志使用
+#[derive(Clone, Debug, Default)]
+pub struct ResolvedKey(pub Option<Result<ApiKey, Status>>);
+
+impl ResolvedKey {
+    pub fn key(&self) -> Option<&ApiKey> {
+        match &self.0 {
+            Some(Ok(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self.key() {
+            Some(v) => v.label.as_str(),
+            None => "-",
+        }
+    }
+}
+
+fn extract_token<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    if let Some(v) = request.headers().get_one("X-API-Key") {
+        return Some(v.trim());
+    }
+    request
+        .headers()
+        .get_one("Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+async fn resolve(request: &Request<'_>) -> ResolvedKey {
+    let token = match extract_token(request) {
+        Some(v) if !v.is_empty() => v,
+        _ => return ResolvedKey(None),
+    };
+    let key = match super::lookup(token).await {
+        Some(v) => v,
+        None => return ResolvedKey(Some(Err(Status::Unauthorized))),
+    };
+    match quota::hit(&key).await {
+        Ok(QuotaState::Ok) => ResolvedKey(Some(Ok(key))),
+        Ok(_) => ResolvedKey(Some(Err(Status::TooManyRequests))),
+        Err(e) => {
+            // 配额存储不可用时不阻断请求
+            warn!("Failed to check quota for {}: {}", key.label, e);
+            ResolvedKey(Some(Ok(key)))
+        }
+    }
+}
+
+pub async fn resolved_key<'r>(request: &'r Request<'_>) -> &'r ResolvedKey {
+    request.local_cache_async(resolve(request)).await
+}
+
+pub trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+pub mod scope {
+    use super::{RequiredScope, Scope};
+
+    pub struct Read;
+    pub struct Purge;
+    pub struct Warm;
+    pub struct Stats;
+
+    impl RequiredScope for Read {
+        const SCOPE: Scope = Scope::Read;
+    }
+
+    impl RequiredScope for Purge {
+        const SCOPE: Scope = Scope::Purge;
+    }
+
+    impl RequiredScope for Warm {
+        const SCOPE: Scope = Scope::Warm;
+    }
+
+    impl RequiredScope for Stats {
+        const SCOPE: Scope = Scope::Stats;
+    }
+}
+
+// 要求请求携带拥有指定权限的 API Key，例如 `_key: Authorized<scope::Purge>`
+pub struct Authorized<S: RequiredScope>(pub ApiKey, PhantomData<S>);
+
+#[rocket::async_trait]
+impl<'r, S: RequiredScope> FromRequest<'r> for Authorized<S> {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+        match &resolved_key(request).await.0 {
+            Some(Ok(key)) if key.scopes.contains(&S::SCOPE) => {
+                Outcome::Success(Authorized(key.clone(), PhantomData))
+            }
+            Some(Ok(key)) => {
+                warn!(
+                    "API key {} lacks the {} scope",
+                    key.label,
+                    S::SCOPE.as_str()
+                );
+                Outcome::Failure((Status::Forbidden, ()))
+            }
+            Some(Err(status)) => Outcome::Failure((*status, ())),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+// 资源路由守卫：仅当 `auth.protect_assets` 开启时要求 read 权限，否则允许匿名访问
+pub struct AssetAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AssetAccess {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+        if CONFIG.auth.protect_assets {
+            return Authorized::<scope::Read>::from_request(request)
+                .await
+                .map(|_| AssetAccess);
+        }
+        match &resolved_key(request).await.0 {
+            Some(Ok(_)) => Outcome::Success(AssetAccess),
+            Some(Err(status)) => Outcome::Failure((*status, ())),
+            None => Outcome::Success(AssetAccess),
+        }
+    }
+}
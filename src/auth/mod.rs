@@ -0,0 +1,112 @@
+pub mod guard;
+pub mod quota;
+
+use crate::conf::auth::{ApiKey, Scope};
+use crate::CONFIG;
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use std::{collections::HashMap, time::Duration};
+use thiserror::Error;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{error, info};
+
+lazy_static! {
+    static ref KEYS: RwLock<HashMap<String, ApiKey>> = RwLock::new(HashMap::new());
+}
+
+// key, label, scopes, daily_requests, daily_bytes
+type ApiKeyRow = (String, String, String, Option<u64>, Option<u64>);
+
+static DB_POOL: OnceCell<MySqlPool> = OnceCell::const_new();
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("AuthError::Database: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("AuthError::Scope: {0}")]
+    Scope(String),
+    #[error("AuthError::Pool: {0}")]
+    RedisPool(#[from] deadpool_redis::PoolError),
+    #[error("AuthError::Redis: {0}")]
+    Redis(#[from] deadpool_redis::redis::RedisError),
+}
+
+async fn get_db_pool() -> Result<&'static MySqlPool, AuthError> {
+    Ok(DB_POOL
+        .get_or_try_init(|| async {
+            MySqlPoolOptions::new()
+                .max_connections(2)
+                .connect(&CONFIG.database.to_uri())
+                .await
+        })
+        .await?)
+}
+
+// 从 MySQL 中读取 API Key，表结构：
+// `key` VARCHAR, `label` VARCHAR, `scopes` VARCHAR（逗号分隔）,
+// `daily_requests` BIGINT UNSIGNED NULL, `daily_bytes` BIGINT UNSIGNED NULL, `enabled` TINYINT
+async fn load_from_database() -> Result<Vec<ApiKey>, AuthError> {
+    let pool = get_db_pool().await?;
+    let rows: Vec<ApiKeyRow> = sqlx::query_as(&format!(
+        "SELECT `key`, `label`, `scopes`, `daily_requests`, `daily_bytes` FROM `{}` WHERE `enabled` = 1",
+        CONFIG.database.table(&CONFIG.auth.table)
+    ))
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter()
+        .map(|(key, label, scopes, daily_requests, daily_bytes)| {
+            Ok(ApiKey {
+                key,
+                label,
+                scopes: scopes
+                    .split(',')
+                    .filter(|v| !v.trim().is_empty())
+                    .map(Scope::try_from)
+                    .collect::<Result<Vec<Scope>, String>>()
+                    .map_err(AuthError::Scope)?,
+                daily_requests,
+                daily_bytes,
+            })
+        })
+        .collect()
+}
+
+pub async fn reload() -> Result<usize, AuthError> {
+    let mut keys: HashMap<String, ApiKey> = CONFIG
+        .auth
+        .keys
+        .iter()
+        .map(|v| (v.key.clone(), v.clone()))
+        .collect();
+    if CONFIG.auth.use_database {
+        for key in load_from_database().await? {
+            keys.insert(key.key.clone(), key);
+        }
+    }
+    let count = keys.len();
+    *KEYS.write().await = keys;
+    Ok(count)
+}
+
+pub async fn lookup(key: &str) -> Option<ApiKey> {
+    KEYS.read().await.get(key).cloned()
+}
+
+// 初始化 API Key 存储，若启用了数据库则定时重新加载
+pub async fn init() -> Result<(), AuthError> {
+    let count = reload().await?;
+    info!("Loaded {} API key(s).", count);
+    if CONFIG.auth.use_database && CONFIG.auth.reload_interval > 0 {
+        tokio::spawn(async {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(CONFIG.auth.reload_interval));
+            interval.tick().await; // 第一次 tick 立即返回
+            loop {
+                interval.tick().await;
+                if let Err(e) = reload().await {
+                    error!("Failed to reload API keys: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use super::AuthError;
+use crate::cache;
+use crate::conf::auth::ApiKey;
+use chrono::Utc;
+use deadpool_redis::redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+
+const QUOTA_EXPIRE: usize = 60 * 60 * 48; // 保留两天，跨时区/跨日时不会过早丢失
+
+// 按 Key 本身（sha256）计数，label 相同的 Key 不共享配额，修改 label 也不会重置配额
+fn quota_key(key: &ApiKey, kind: &str) -> String {
+    let hash: &[u8] = &Sha256::digest(key.key.as_bytes());
+    cache::namespaced(&format!(
+        "quota:{}:{}:{}",
+        base16ct::lower::encode_string(hash),
+        Utc::now().format("%Y%m%d"),
+        kind
+    ))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuotaState {
+    Ok,
+    RequestsExceeded,
+    BytesExceeded,
+}
+
+// 记录一次请求，并检查当日配额
+pub async fn hit(key: &ApiKey) -> Result<QuotaState, AuthError> {
+    if key.daily_requests.is_none() && key.daily_bytes.is_none() {
+        return Ok(QuotaState::Ok);
+    }
+    // 缓存不可用时不限制
+    let mut conn = match cache::try_get_connection().await {
+        Some(v) => v,
+        None => return Ok(QuotaState::Ok),
+    };
+    let requests_key = quota_key(key, "requests");
+    let requests: u64 = conn.incr(&requests_key, 1).await?;
+    if requests == 1 {
+        let _: () = conn.expire(&requests_key, QUOTA_EXPIRE).await?;
+    }
+    if let Some(limit) = key.daily_requests {
+        if requests > limit {
+            return Ok(QuotaState::RequestsExceeded);
+        }
+    }
+    if let Some(limit) = key.daily_bytes {
+        let bytes: Option<u64> = conn.get(quota_key(key, "bytes")).await?;
+        if bytes.unwrap_or(0) >= limit {
+            return Ok(QuotaState::BytesExceeded);
+        }
+    }
+    Ok(QuotaState::Ok)
+}
+
+// 累计当日流量
+pub async fn add_bytes(key: &ApiKey, bytes: u64) -> Result<(), AuthError> {
+    if key.daily_bytes.is_none() || bytes == 0 {
+        return Ok(());
+    }
+    let mut conn = match cache::try_get_connection().await {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let bytes_key = quota_key(key, "bytes");
+    let total: u64 = conn.incr(&bytes_key, bytes).await?;
+    if total == bytes {
+        let _: () = conn.expire(&bytes_key, QUOTA_EXPIRE).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use super::index::jsdelivr::{
+    check_request, error_response, forget_jsdelivr_resource, query::ForwardedQuery,
+    refresh_jsdelivr_resource, validate::validate,
+};
+use crate::auth::guard::{scope, Authorized};
+use crate::stats::snapshot;
+use crate::utils::response::{success, APIResponse};
+use rocket::{
+    get,
+    http::uri::Origin,
+    post,
+    serde::json::{serde_json::json, Value},
+};
+use std::path::PathBuf;
+use tracing::{error, info};
+
+#[post("/purge/<path..>")]
+pub async fn purge(
+    key: Authorized<scope::Purge>,
+    query: ForwardedQuery,
+    uri: &Origin<'_>,
+    path: PathBuf,
+) -> APIResponse<Value> {
+    info!("{} purges {}", key.0.label, path.display());
+    let result = match validate(uri.path().as_str(), &path) {
+        Ok(_) => forget_jsdelivr_resource(path, &query).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(deleted) => success(json!({ "deleted": deleted })),
+        Err(ref e) => {
+            error!("{:?}", e);
+            error_response(e)
+        }
+    }
+}
+
+#[post("/warm/<path..>")]
+pub async fn warm(
+    key: Authorized<scope::Warm>,
+    query: ForwardedQuery,
+    uri: &Origin<'_>,
+    path: PathBuf,
+) -> APIResponse<Value> {
+    info!("{} warms {}", key.0.label, path.display());
+    let result = match check_request(uri.path().as_str(), &path) {
+        Ok(_) => refresh_jsdelivr_resource(path, &query, None).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(resource) => success(json!({ "mime": resource.mime, "size": resource.data.len() })),
+        Err(ref e) => {
+            error!("{:?}", e);
+            error_response(e)
+        }
+    }
+}
+
+#[get("/stats")]
+pub fn stats(_key: Authorized<scope::Stats>) -> APIResponse<Value> {
+    success(snapshot())
+}
@@ -0,0 +1,336 @@
+use crate::CONFIG;
+use bytes::Bytes;
+use flate2::{
+    write::{DeflateEncoder, GzEncoder},
+    Compression,
+};
+use std::io::{self, Write};
+
+// 归档中所有文件使用固定的修改时间（1980-01-01），相同的版本生成的归档内容一致
+const TAR_MTIME: u64 = 315532800;
+const ZIP_DATE: u16 = (1 << 5) | 1; // DOS 日期：1980 年 1 月 1 日
+const ZIP_TIME: u16 = 0;
+// zip 不使用 zip64 扩展，文件数量与偏移量受限
+pub const ZIP_MAX_FILES: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    // 从请求路径的后缀识别格式，返回去掉后缀的路径
+    pub fn strip(path: &str) -> Option<(Self, &str)> {
+        [
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".zip", ArchiveFormat::Zip),
+        ]
+        .into_iter()
+        .find_map(|(suffix, format)| path.strip_suffix(suffix).map(|v| (format, v)))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "application/gzip",
+            ArchiveFormat::Zip => "application/zip",
+        }
+    }
+
+    // 文件名能否写入归档：ustar 的文件名最多 100 字节，加上前缀最多 255 字节
+    pub fn supports_name(&self, name: &str) -> bool {
+        match self {
+            ArchiveFormat::TarGz => split_tar_name(name).is_some(),
+            ArchiveFormat::Zip => name.len() <= u16::MAX as usize,
+        }
+    }
+}
+
+fn split_tar_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+    name.match_indices('/')
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && !name.is_empty() && name.len() <= 100)
+}
+
+// 以八进制写入定长字段，末尾保留一个 NUL
+fn write_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let value = format!("{:0width$o}", value, width = width);
+    field[..width].copy_from_slice(value.as_bytes());
+    field[width] = 0;
+}
+
+fn tar_header(name: &str, size: usize) -> io::Result<[u8; 512]> {
+    let (prefix, name) = split_tar_name(name).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "file name is too long for tar")
+    })?;
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644); // mode
+    write_octal(&mut header[108..116], 0); // uid
+    write_octal(&mut header[116..124], 0); // gid
+    write_octal(&mut header[124..136], size as u64);
+    write_octal(&mut header[136..148], TAR_MTIME);
+    header[156] = b'0'; // 普通文件
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    // 校验和按校验和字段为空格计算
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|v| *v as u64).sum();
+    write_octal(&mut header[148..155], checksum);
+    Ok(header)
+}
+
+// 不使用数据描述符：每个文件在写入前已完整读取，可以先计算 CRC 与大小
+pub struct ZipWriter {
+    compression: Compression,
+    offset: u64,
+    central: Vec<u8>, // 中央目录，在所有文件之后写入
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn append(&mut self, name: &str, data: &[u8]) -> io::Result<Vec<u8>> {
+        let crc = crc32fast::hash(data);
+        let mut encoder = DeflateEncoder::new(Vec::with_capacity(data.len() / 2), self.compression);
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        // 压缩后没有变小的文件（如图片）直接存储
+        let (method, body): (u16, &[u8]) = if compressed.len() < data.len() {
+            (8, &compressed)
+        } else {
+            (0, data)
+        };
+        let offset = u32::try_from(self.offset)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "zip exceeds 4 GiB"))?;
+        let name = name.as_bytes();
+        let mut fields = vec![];
+        fields.extend(20u16.to_le_bytes()); // 解压所需版本
+        fields.extend(0x0800u16.to_le_bytes()); // 文件名使用 UTF-8
+        fields.extend(method.to_le_bytes());
+        fields.extend(ZIP_TIME.to_le_bytes());
+        fields.extend(ZIP_DATE.to_le_bytes());
+        fields.extend(crc.to_le_bytes());
+        fields.extend((body.len() as u32).to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes()); // 扩展字段长度
+
+        let mut local = Vec::with_capacity(30 + name.len() + body.len());
+        local.extend(0x04034b50u32.to_le_bytes());
+        local.extend(&fields);
+        local.extend(name);
+        local.extend(body);
+
+        self.central.extend(0x02014b50u32.to_le_bytes());
+        self.central.extend(20u16.to_le_bytes()); // 创建版本
+        self.central.extend(&fields);
+        self.central.extend(0u16.to_le_bytes()); // 注释长度
+        self.central.extend(0u16.to_le_bytes()); // 磁盘编号
+        self.central.extend(0u16.to_le_bytes()); // 内部属性
+        self.central.extend(0u32.to_le_bytes()); // 外部属性
+        self.central.extend(offset.to_le_bytes());
+        self.central.extend(name);
+
+        self.offset += local.len() as u64;
+        self.entries = self
+            .entries
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "too many files for zip"))?;
+        Ok(local)
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        let offset = u32::try_from(self.offset)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "zip exceeds 4 GiB"))?;
+        let mut end = self.central;
+        let size = end.len() as u32;
+        end.extend(0x06054b50u32.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // 磁盘编号
+        end.extend(0u16.to_le_bytes()); // 中央目录所在的磁盘
+        end.extend(self.entries.to_le_bytes());
+        end.extend(self.entries.to_le_bytes());
+        end.extend(size.to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // 注释长度
+        Ok(end)
+    }
+}
+
+// 逐个文件写入归档，每次返回可以立即发送的数据，不在内存中保留整个归档
+pub enum ArchiveWriter {
+    TarGz(GzEncoder<Vec<u8>>),
+    Zip(ZipWriter),
+}
+
+impl ArchiveWriter {
+    pub fn new(format: ArchiveFormat) -> Self {
+        ArchiveWriter::with_compression(format, Compression::new(CONFIG.compression.gzip_level))
+    }
+
+    fn with_compression(format: ArchiveFormat, compression: Compression) -> Self {
+        match format {
+            ArchiveFormat::TarGz => ArchiveWriter::TarGz(GzEncoder::new(vec![], compression)),
+            ArchiveFormat::Zip => ArchiveWriter::Zip(ZipWriter {
+                compression,
+                offset: 0,
+                central: vec![],
+                entries: 0,
+            }),
+        }
+    }
+
+    pub fn append(&mut self, name: &str, data: &[u8]) -> io::Result<Bytes> {
+        match self {
+            ArchiveWriter::TarGz(encoder) => {
+                encoder.write_all(&tar_header(name, data.len())?)?;
+                encoder.write_all(data)?;
+                let padding = (512 - data.len() % 512) % 512;
+                encoder.write_all(&[0u8; 512][..padding])?;
+                // 取出已压缩的部分，压缩器内部的状态保留到下一个文件
+                Ok(Bytes::from(std::mem::take(encoder.get_mut())))
+            }
+            ArchiveWriter::Zip(writer) => writer.append(name, data).map(Bytes::from),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<Bytes> {
+        match self {
+            ArchiveWriter::TarGz(mut encoder) => {
+                // 归档以两个空块结束
+                encoder.write_all(&[0u8; 1024])?;
+                encoder.finish().map(Bytes::from)
+            }
+            ArchiveWriter::Zip(writer) => writer.finish().map(Bytes::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    // 伪随机内容，压缩后不会变小
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x2545f491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn files() -> Vec<(String, Vec<u8>)> {
+        vec![
+            (
+                "package/index.js".to_string(),
+                b"module.exports = 1;\n".repeat(100),
+            ),
+            ("package/empty.txt".to_string(), vec![]),
+            ("package/dist/noise.bin".to_string(), noise(513)),
+            // 超过 100 字节，需要拆分到 ustar 前缀中
+            (
+                format!("package/{}/{}.js", "nested".repeat(20), "a".repeat(90)),
+                b"export default 2;".to_vec(),
+            ),
+            ("package/说明.md".to_string(), "你好".as_bytes().to_vec()),
+        ]
+    }
+
+    fn write(format: ArchiveFormat, files: &[(String, Vec<u8>)]) -> Vec<u8> {
+        let mut writer = ArchiveWriter::with_compression(format, Compression::default());
+        let mut output = vec![];
+        for (name, data) in files {
+            assert!(format.supports_name(name));
+            output.extend(writer.append(name, data).unwrap());
+        }
+        output.extend(writer.finish().unwrap());
+        output
+    }
+
+    #[test]
+    fn strips_archive_suffix() {
+        assert_eq!(
+            ArchiveFormat::strip("npm/jquery@3.tar.gz"),
+            Some((ArchiveFormat::TarGz, "npm/jquery@3"))
+        );
+        assert_eq!(
+            ArchiveFormat::strip("npm/jquery@3/dist.tgz"),
+            Some((ArchiveFormat::TarGz, "npm/jquery@3/dist"))
+        );
+        assert_eq!(
+            ArchiveFormat::strip("npm/jquery@3.zip"),
+            Some((ArchiveFormat::Zip, "npm/jquery@3"))
+        );
+        assert_eq!(ArchiveFormat::strip("npm/jquery@3"), None);
+    }
+
+    #[test]
+    fn rejects_names_tar_cannot_store() {
+        assert!(!ArchiveFormat::TarGz.supports_name(&"a".repeat(101)));
+        assert!(!ArchiveFormat::TarGz.supports_name(&format!("{}/a.js", "a".repeat(156))));
+        assert!(ArchiveFormat::Zip.supports_name(&"a".repeat(101)));
+    }
+
+    #[test]
+    fn tar_gz_round_trip() {
+        let files = files();
+        let output = write(ArchiveFormat::TarGz, &files);
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&output[..]));
+        let mut entries = archive.entries().unwrap();
+        for (name, data) in &files {
+            let mut entry = entries.next().unwrap().unwrap();
+            let header = entry.header();
+            assert_eq!(header.entry_type(), tar::EntryType::Regular);
+            assert_eq!(header.mode().unwrap(), 0o644);
+            assert_eq!(header.mtime().unwrap(), TAR_MTIME);
+            assert_eq!(header.size().unwrap(), data.len() as u64);
+            // 校验和在读取头部时验证
+            assert_eq!(entry.path().unwrap().to_str(), Some(name.as_str()));
+            let mut content = vec![];
+            entry.read_to_end(&mut content).unwrap();
+            assert_eq!(&content, data);
+        }
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn zip_round_trip() {
+        let files = files();
+        let output = write(ArchiveFormat::Zip, &files);
+        let mut archive = zip::ZipArchive::new(Cursor::new(output)).unwrap();
+        assert_eq!(archive.len(), files.len());
+        for (index, (name, data)) in files.iter().enumerate() {
+            let mut entry = archive.by_index(index).unwrap();
+            assert_eq!(entry.name(), name);
+            assert_eq!(entry.size(), data.len() as u64);
+            // 读取到结尾时校验 CRC
+            let mut content = vec![];
+            entry.read_to_end(&mut content).unwrap();
+            assert_eq!(&content, data);
+        }
+        // 可压缩的文件使用 deflate，压缩后没有变小的文件直接存储
+        let mut method = |name| archive.by_name(name).unwrap().compression();
+        assert_eq!(method("package/index.js"), zip::CompressionMethod::Deflated);
+        assert_eq!(
+            method("package/dist/noise.bin"),
+            zip::CompressionMethod::Stored
+        );
+    }
+}
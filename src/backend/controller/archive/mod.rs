@@ -0,0 +1,209 @@
+pub mod format;
+
+use super::index::jsdelivr::{
+    check_request, error_response, key::normalize_path, listing::package_tree,
+    package::PackageSpec, query::ForwardedQuery, remember_jsdelivr_resource, resolve,
+    types::FetchJSDelivrFailureError,
+};
+use crate::auth::guard::AssetAccess;
+use crate::utils::response::APIResponse;
+use crate::CONFIG;
+use bytes::Bytes;
+use futures::stream;
+use rocket::{
+    get,
+    http::{uri::Origin, ContentType, Header},
+    response,
+    serde::json::Value,
+    Request, Responder, Response,
+};
+use std::{io, path::PathBuf, str::FromStr};
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
+use tracing::{error, instrument};
+
+use self::format::{ArchiveFormat, ArchiveWriter, ZIP_MAX_FILES};
+
+struct ArchiveFile {
+    name: String,  // 归档中的文件名
+    path: PathBuf, // 确切版本的请求路径
+}
+
+// 流式返回的归档
+pub struct Archive {
+    format: ArchiveFormat,
+    filename: String,
+    reader: Box<dyn AsyncRead + Send + Unpin>,
+}
+
+impl<'r> response::Responder<'r, 'static> for Archive {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::from_str(self.format.mime()).unwrap_or(ContentType::Binary))
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .streamed_body(self.reader)
+            .ok()
+    }
+}
+
+#[derive(Responder)]
+pub enum ArchiveResponse {
+    Json(APIResponse<Value>),
+    Archive(Archive),
+}
+
+// 依次通过单个文件的缓存读取并写入归档，实际大小超过限制时中断。
+// 出错时流以错误结束且不写入归档结尾，Rocket 会中断连接（分块传输没有结束块），
+// 客户端得到的是失败的下载而不是被截断的 200 响应
+fn stream_archive(
+    format: ArchiveFormat,
+    files: Vec<ArchiveFile>,
+) -> impl futures::Stream<Item = io::Result<Bytes>> + Send + 'static {
+    stream::try_unfold(
+        Some((ArchiveWriter::new(format), files.into_iter(), 0usize)),
+        |state| async move {
+            let (mut writer, mut files, total) = match state {
+                Some(v) => v,
+                None => return Ok(None),
+            };
+            let file = match files.next() {
+                Some(v) => v,
+                None => {
+                    let end = tokio::task::spawn_blocking(move || writer.finish())
+                        .await
+                        .map_err(io::Error::other)??;
+                    return Ok(Some((end, None)));
+                }
+            };
+            let data = match remember_jsdelivr_resource(
+                file.path.clone(),
+                &ForwardedQuery::default(),
+                None,
+            )
+            .await
+            {
+                Ok(v) => v.data.into_bytes().await?,
+                Err(e) => {
+                    error!(
+                        "Failed to add {} to archive, aborting the download: {}",
+                        file.path.display(),
+                        e
+                    );
+                    return Err(io::Error::other(e));
+                }
+            };
+            let total = total + data.len();
+            if total > CONFIG.archive.max_size {
+                error!(
+                    "Archive exceeds the size limit at {}, aborting the download",
+                    file.path.display()
+                );
+                return Err(io::Error::other("archive exceeds the size limit"));
+            }
+            // 压缩会阻塞，写入器移入阻塞线程池后再取回
+            let (writer, chunk) = tokio::task::spawn_blocking(move || {
+                let chunk = writer.append(&file.name, &data);
+                (writer, chunk)
+            })
+            .await
+            .map_err(io::Error::other)?;
+            Ok(Some((chunk?, Some((writer, files, total)))))
+        },
+    )
+}
+
+// 解析版本并按数据 API 的文件列表准备归档，超过限制的请求在开始发送前拒绝
+async fn prepare_archive(
+    uri: &Origin<'_>,
+    path: PathBuf,
+) -> Result<Archive, FetchJSDelivrFailureError> {
+    let raw = path.to_str().ok_or(FetchJSDelivrFailureError::PathCovert)?;
+    let (format, stripped) = ArchiveFormat::strip(raw.trim_end_matches('/')).ok_or(
+        FetchJSDelivrFailureError::InvalidPath("archive must end with .tar.gz, .tgz or .zip"),
+    )?;
+    let path = PathBuf::from(stripped);
+    check_request(uri.path().as_str(), &path)?;
+    let resolved = resolve::resolve_path(path).await?;
+    let spec = PackageSpec::parse(&normalize_path(&resolved.path)).ok_or(
+        FetchJSDelivrFailureError::InvalidPath("archive requires a package"),
+    )?;
+    let version = spec
+        .version
+        .clone()
+        .ok_or(FetchJSDelivrFailureError::InvalidPath(
+            "archive requires a version",
+        ))?;
+    let tree = package_tree(&spec, &version).await?;
+
+    let dir = spec.file.trim_matches('/');
+    let prefix = if dir.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", dir)
+    };
+    let mut files = vec![];
+    let mut size = 0;
+    for file in tree["files"].as_array().into_iter().flatten() {
+        let name = match file["name"].as_str() {
+            Some(v) if v.starts_with(&prefix) => v,
+            _ => continue,
+        };
+        size += file["size"].as_u64().unwrap_or(0) as usize;
+        files.push(ArchiveFile {
+            name: format!("package{}", name),
+            path: PathBuf::from(format!("{}@{}{}", spec.id(), version, name)),
+        });
+    }
+    if files.is_empty() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(404));
+    }
+    let max_files = match format {
+        ArchiveFormat::Zip => CONFIG.archive.max_files.min(ZIP_MAX_FILES),
+        ArchiveFormat::TarGz => CONFIG.archive.max_files,
+    };
+    if files.len() > max_files || size > CONFIG.archive.max_size {
+        return Err(FetchJSDelivrFailureError::ArchiveTooLarge(
+            files.len(),
+            size,
+        ));
+    }
+    if files.iter().any(|v| !format.supports_name(&v.name)) {
+        return Err(FetchJSDelivrFailureError::InvalidPath(
+            "package contains a file name the archive format cannot store",
+        ));
+    }
+
+    let mut filename = format!(
+        "{}-{}",
+        spec.name.rsplit('/').next().unwrap_or_default(),
+        version
+    );
+    if !dir.is_empty() {
+        filename = format!("{}-{}", filename, dir.replace('/', "-"));
+    }
+    Ok(Archive {
+        format,
+        filename: format!(
+            "{}.{}",
+            filename.replace(|c: char| c == '"' || c.is_control(), "_"),
+            format.extension()
+        ),
+        reader: Box::new(StreamReader::new(Box::pin(stream_archive(format, files)))),
+    })
+}
+
+// 打包下载包的某个版本或其中的目录，如 /archive/npm/jquery@3.tar.gz、/archive/npm/jquery@3/dist.zip
+#[get("/<path..>")]
+#[instrument(skip(_access, uri))]
+pub async fn get(_access: AssetAccess, uri: &Origin<'_>, path: PathBuf) -> ArchiveResponse {
+    match prepare_archive(uri, path).await {
+        Ok(v) => ArchiveResponse::Archive(v),
+        Err(ref e) => {
+            error!("{:?}", e);
+            ArchiveResponse::Json(error_response(e))
+        }
+    }
+}
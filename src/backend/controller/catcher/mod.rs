@@ -0,0 +1,19 @@
+use crate::utils::response::{fail_with_code, APIResponse};
+use rocket::{catch, http::Status, serde::json::Value, Request};
+
+// 请求守卫失败（401/403/429 等）时也使用统一的响应格式
+#[catch(default)]
+pub fn default(status: Status, _request: &Request) -> APIResponse<Value> {
+    fail_with_code(
+        status.code as i64,
+        match status.code {
+            401 => Some("UNAUTHORIZED"),
+            403 => Some("FORBIDDEN"),
+            404 => Some("NOT_FOUND"),
+            429 => Some("QUOTA_EXCEEDED"),
+            _ => None,
+        },
+        None,
+        "".into(),
+    )
+}
@@ -0,0 +1,197 @@
+use super::index::jsdelivr::{
+    build_client, error_response,
+    response::{store_resource, CacheStatus, Resource},
+    rules,
+    types::FetchJSDelivrFailureError,
+    validate::validate_raw,
+    JSDelivrResponse,
+};
+use crate::auth::guard::AssetAccess;
+use crate::cache::chunk::Body;
+use crate::cache::entry::{self, EntryMeta};
+use crate::stats::{self, STATS};
+use crate::utils::compress::{AcceptEncoding, Encoding};
+use crate::utils::response::success;
+use crate::{cache, CONFIG};
+use bytes::Bytes;
+use reqwest::Url;
+use rocket::{
+    get,
+    http::uri::Origin,
+    serde::json::{serde_json, Value},
+};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::{error, instrument, warn};
+use url::form_urlencoded;
+
+const DATA_MIME: &str = "application/json; charset=utf-8";
+
+fn cache_key(path: &Path, query: &[(String, String)]) -> String {
+    let mut raw = path.to_string_lossy().to_string();
+    if !query.is_empty() {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(query)
+            .finish();
+        raw = format!("{}?{}", raw, query);
+    }
+    let hash: &[u8] = &Sha256::digest(raw.as_bytes());
+    cache::namespaced(&format!(
+        "data:v{}:{}",
+        CONFIG.cache.key_version,
+        base16ct::lower::encode_string(hash)
+    ))
+}
+
+fn data_url(path: &Path, query: &[(String, String)]) -> Result<Url, FetchJSDelivrFailureError> {
+    let mut url = Url::parse(&CONFIG.jsdelivr.data_api)?;
+    url.path_segments_mut()
+        .map_err(|_| FetchJSDelivrFailureError::InvalidPath("invalid data API base URL"))?
+        .pop_if_empty()
+        .extend(path.iter().map(|v| v.to_string_lossy()));
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    Ok(url)
+}
+
+async fn fetch_data(
+    path: &Path,
+    query: &[(String, String)],
+) -> Result<Bytes, FetchJSDelivrFailureError> {
+    let response = build_client()?
+        .get(data_url(path, query)?)
+        .header("Accept", "application/json")
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(
+            status.as_u16(),
+        ));
+    }
+    let data = response.bytes().await?;
+    // 只缓存合法的 JSON
+    serde_json::from_slice::<Value>(&data)?;
+    Ok(data)
+}
+
+// 读取数据 API 的响应，缓存过期后回源，上游故障时返回过期内容
+pub(crate) async fn remember_data(
+    path: &Path,
+    query: &[(String, String)],
+    encoding: Option<Encoding>,
+) -> Result<Resource, FetchJSDelivrFailureError> {
+    let key = cache_key(path, query);
+    let mut stale: Option<(EntryMeta, Body, Option<Encoding>)> = None;
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::read(&mut conn, &key, encoding).await {
+            Ok(Some((meta, data, encoding))) => {
+                cache::report_success();
+                if meta.is_fresh() {
+                    stats::incr(&STATS.cache_hits);
+                    let age = meta.age();
+                    return Ok(Resource::new(meta.mime, data, CacheStatus::Hit, age)
+                        .with_encoding(encoding));
+                }
+                stale = Some((meta, data, encoding));
+            }
+            Ok(None) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    stats::incr(&STATS.cache_misses);
+    stats::incr(&STATS.upstream_fetches);
+    let data = match (fetch_data(path, query).await, stale) {
+        (Ok(v), _) => v,
+        (Err(e), Some((meta, data, encoding))) if e.is_upstream_failure() => {
+            warn!("Data API failed, serving stale content: {}", e);
+            stats::incr(&STATS.upstream_errors);
+            stats::incr(&STATS.stale_served);
+            let age = meta.age();
+            return Ok(
+                Resource::new(meta.mime, data, CacheStatus::Stale, age).with_encoding(encoding)
+            );
+        }
+        (Err(e), _) => {
+            stats::incr(&STATS.upstream_errors);
+            return Err(e);
+        }
+    };
+    let meta = EntryMeta::new(DATA_MIME.into(), CONFIG.data.ttl as u64);
+    let expire = CONFIG.data.ttl + CONFIG.cache.stale_if_error;
+    Ok(store_resource(&key, meta, data, false, expire, encoding).await)
+}
+
+// 包的数据（如 `v1/packages/npm/jquery@3`、`v1/stats/packages/gh/user/repo`）与资源一样受 [access] 规则限制
+fn check_package(path: &Path) -> Result<(), FetchJSDelivrFailureError> {
+    let segments: Vec<&str> = path.iter().filter_map(|v| v.to_str()).collect();
+    match segments
+        .iter()
+        .position(|v| *v == "packages" || *v == "package")
+    {
+        Some(i) if segments.len() > i + 2 => {
+            rules::check(&segments[i + 1..].iter().collect::<PathBuf>())
+        }
+        _ => Ok(()),
+    }
+}
+
+// 读取并解析数据 API 的 JSON 响应
+pub(crate) async fn data_json(
+    path: &Path,
+    query: &[(String, String)],
+) -> Result<Value, FetchJSDelivrFailureError> {
+    let data = remember_data(path, query, None)
+        .await?
+        .data
+        .into_bytes()
+        .await?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+// 代理 jsDelivr 数据 API，如 /data/v1/packages/npm/jquery。
+// `wrap` 参数决定是否包装为统一的响应格式，data.forward_query 中的参数转发给上游并参与缓存键
+#[get("/<path..>")]
+#[instrument(skip(_access, accept, uri))]
+pub async fn get(
+    _access: AssetAccess,
+    accept: AcceptEncoding,
+    uri: &Origin<'_>,
+    path: PathBuf,
+) -> JSDelivrResponse {
+    let mut wrap = CONFIG.data.wrap;
+    let mut query: Vec<(String, String)> = vec![];
+    if let Some(raw) = uri.query() {
+        for (k, v) in form_urlencoded::parse(raw.as_str().as_bytes()) {
+            match k.as_ref() {
+                "wrap" => wrap = v != "false" && v != "0",
+                k if CONFIG.data.forward_query.iter().any(|v| v == k) => {
+                    query.push((k.to_string(), v.into_owned()))
+                }
+                _ => {}
+            }
+        }
+    }
+    query.sort();
+    query.dedup();
+    if let Err(ref e) = validate_raw(uri.path().as_str()).and_then(|_| check_package(&path)) {
+        return JSDelivrResponse::Json(error_response(e));
+    }
+    let result = if wrap {
+        data_json(&path, &query)
+            .await
+            .map(|v| JSDelivrResponse::Json(success(v)))
+    } else {
+        remember_data(&path, &query, accept.0)
+            .await
+            .map(|v| JSDelivrResponse::Raw(Box::new(v)))
+    };
+    match result {
+        Ok(v) => v,
+        Err(ref e) => {
+            error!("{:?}", e);
+            JSDelivrResponse::Json(error_response(e))
+        }
+    }
+}
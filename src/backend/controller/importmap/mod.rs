@@ -0,0 +1,227 @@
+use super::index::jsdelivr::{
+    check_request, error_response, package::PackageSpec, query::ForwardedQuery, redirect_location,
+    remember_jsdelivr_resource, resolve, resource_integrity, rules,
+    types::FetchJSDelivrFailureError,
+};
+use super::sri::public_url;
+use crate::auth::guard::AssetAccess;
+use crate::utils::response::{success, APIResponse};
+use futures::future::try_join_all;
+use rocket::{
+    get,
+    http::uri::{Host, Origin},
+    post,
+    serde::json::{serde_json, serde_json::json, Json, Value},
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{error, instrument};
+use url::form_urlencoded;
+
+// 单次请求最多包含的包数量
+const MAX_PACKAGES: usize = 64;
+// exports 中按顺序匹配的条件，优先选择浏览器可直接加载的 ES 模块
+const EXPORT_CONDITIONS: [&str; 4] = ["browser", "import", "module", "default"];
+
+// 包列表可以是 `["react@18", "vue"]`，也可以是 `{ "react": "^18", "vue": "" }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Packages {
+    List(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+#[derive(Deserialize)]
+pub struct ImportMapRequest {
+    pub packages: Packages,
+    #[serde(default)]
+    pub integrity: bool, // 是否同时返回入口文件的 SRI 摘要
+}
+
+impl Packages {
+    fn into_list(self) -> Vec<String> {
+        match self {
+            Packages::List(v) => v,
+            Packages::Map(v) => v
+                .into_iter()
+                .map(|(name, range)| match range.trim() {
+                    "" => name,
+                    range => format!("{}@{}", name, range),
+                })
+                .collect(),
+        }
+    }
+}
+
+struct Entry {
+    name: String,
+    url: String,   // 入口文件的地址
+    scope: String, // 包目录的地址，用于 `name/` 形式的子路径导入
+    integrity: Option<String>,
+}
+
+// 在 exports 的条件对象中查找入口文件，条件可以嵌套
+fn export_target(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(v) => Some(v),
+        Value::Object(map) => EXPORT_CONDITIONS
+            .iter()
+            .find_map(|v| map.get(*v).and_then(export_target)),
+        _ => None,
+    }
+}
+
+// 按 package.json 查找 ES 模块的入口：exports["."] > module > browser > main > index.js
+fn entry_point(package: &Value) -> String {
+    let exports = &package["exports"];
+    let from_exports = match exports {
+        Value::Object(map) if map.keys().any(|v| v.starts_with('.')) => {
+            map.get(".").and_then(export_target)
+        }
+        v => export_target(v),
+    };
+    let entry = from_exports
+        .or_else(|| package["module"].as_str())
+        .or_else(|| package["browser"].as_str())
+        .or_else(|| package["main"].as_str())
+        .unwrap_or("index.js");
+    let entry = entry.trim_start_matches("./").trim_start_matches('/');
+    // main 字段可以省略扩展名
+    if Path::new(entry).extension().is_none() {
+        format!("{}.js", entry)
+    } else {
+        entry.to_string()
+    }
+}
+
+async fn resolve_package(
+    package: String,
+    host: Option<&Host<'_>>,
+    integrity: bool,
+) -> Result<Entry, FetchJSDelivrFailureError> {
+    let path = PathBuf::from(format!("npm/{}", package.trim().trim_matches('/')));
+    check_request(&path.to_string_lossy(), &path)?;
+    let mut spec = PackageSpec::parse(&path)
+        .filter(|v| v.file.is_empty())
+        .ok_or(FetchJSDelivrFailureError::InvalidPath(
+            "package must be specified as name@range",
+        ))?;
+    if let Some(version) = resolve::resolve(&spec).await? {
+        spec.version = Some(version);
+    }
+    rules::check_version(&spec)?;
+
+    spec.file = "package.json".into();
+    let metadata = remember_jsdelivr_resource(spec.to_path(), &ForwardedQuery::default(), None)
+        .await?
+        .data
+        .into_bytes()
+        .await?;
+    spec.file = entry_point(&serde_json::from_slice(&metadata)?);
+    let entry_path = spec.to_path();
+    let integrity = if integrity {
+        let query = ForwardedQuery::default();
+        let resource = remember_jsdelivr_resource(entry_path.clone(), &query, None).await?;
+        Some(
+            resource_integrity(&entry_path, &query, resource)
+                .await?
+                .sha384,
+        )
+    } else {
+        None
+    };
+    let url = public_url(host, redirect_location(&entry_path, false, None));
+    spec.file = String::new();
+    let scope = public_url(host, redirect_location(&spec.to_path(), true, None));
+    Ok(Entry {
+        name: spec.name,
+        url,
+        scope,
+        integrity,
+    })
+}
+
+// 生成 importmap，所有地址都指向本代理上确切版本的文件
+async fn import_map(
+    packages: Vec<String>,
+    host: Option<&Host<'_>>,
+    integrity: bool,
+) -> Result<Value, FetchJSDelivrFailureError> {
+    if packages.is_empty() {
+        return Err(FetchJSDelivrFailureError::InvalidPath(
+            "at least one package is required",
+        ));
+    }
+    if packages.len() > MAX_PACKAGES {
+        return Err(FetchJSDelivrFailureError::InvalidPath("too many packages"));
+    }
+    let entries = try_join_all(
+        packages
+            .into_iter()
+            .map(|v| resolve_package(v, host, integrity)),
+    )
+    .await?;
+    let mut imports = BTreeMap::new();
+    let mut hashes = BTreeMap::new();
+    for entry in entries {
+        if let Some(v) = entry.integrity {
+            hashes.insert(entry.url.clone(), v);
+        }
+        imports.insert(format!("{}/", entry.name), entry.scope);
+        imports.insert(entry.name, entry.url);
+    }
+    let mut map = json!({ "imports": imports });
+    if integrity {
+        map["integrity"] = json!(hashes);
+    }
+    Ok(map)
+}
+
+fn respond(result: Result<Value, FetchJSDelivrFailureError>) -> APIResponse<Value> {
+    match result {
+        Ok(v) => success(v),
+        Err(ref e) => {
+            error!("{:?}", e);
+            error_response(e)
+        }
+    }
+}
+
+// 如 /importmap?packages=react@18,vue@3&integrity=true，packages 可以重复出现
+#[get("/")]
+#[instrument(skip(_access, uri, host))]
+pub async fn get(
+    _access: AssetAccess,
+    uri: &Origin<'_>,
+    host: Option<&Host<'_>>,
+) -> APIResponse<Value> {
+    let mut packages = vec![];
+    let mut integrity = false;
+    if let Some(raw) = uri.query() {
+        for (k, v) in form_urlencoded::parse(raw.as_str().as_bytes()) {
+            match k.as_ref() {
+                "packages" => packages.extend(
+                    v.split(',')
+                        .filter(|v| !v.trim().is_empty())
+                        .map(|v| v.to_string()),
+                ),
+                "integrity" => integrity = v != "false" && v != "0",
+                _ => {}
+            }
+        }
+    }
+    respond(import_map(packages, host, integrity).await)
+}
+
+// 请求体为 ImportMapRequest，如 `{ "packages": { "react": "^18" }, "integrity": true }`
+#[post("/", data = "<request>")]
+#[instrument(skip(_access, host, request))]
+pub async fn post(
+    _access: AssetAccess,
+    host: Option<&Host<'_>>,
+    request: Json<ImportMapRequest>,
+) -> APIResponse<Value> {
+    let request = request.into_inner();
+    respond(import_map(request.packages.into_list(), host, request.integrity).await)
+}
@@ -0,0 +1,169 @@
+use super::key::cache_key;
+use super::query::ForwardedQuery;
+use super::resolve;
+use super::response::{store_resource, CacheStatus, Resource};
+use super::types::FetchJSDelivrFailureError;
+use super::{check_request, remember_jsdelivr_resource};
+use crate::cache::entry::{self, EntryMeta};
+use crate::stats::{self, STATS};
+use crate::utils::compress::Encoding;
+use crate::{cache, CONFIG};
+use bytes::Bytes;
+use futures::{stream, StreamExt, TryStreamExt};
+use std::path::{Path, PathBuf};
+
+const NAMESPACE: &str = "combine";
+// 单次合并最多包含的文件数量
+const MAX_PARTS: usize = 50;
+// 同时获取的文件数量
+const FETCH_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CombineKind {
+    Js,
+    Css,
+}
+
+impl CombineKind {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "js" | "mjs" | "cjs" => Some(CombineKind::Js),
+            "css" => Some(CombineKind::Css),
+            _ => None,
+        }
+    }
+
+    fn mime(&self) -> &'static str {
+        match self {
+            CombineKind::Js => "application/javascript; charset=utf-8",
+            CombineKind::Css => "text/css; charset=utf-8",
+        }
+    }
+
+    // 文件之间的分隔符。JS 文件末尾可能缺少分号或以单行注释结尾，需要换行并补充分号
+    fn separator(&self) -> &'static [u8] {
+        match self {
+            CombineKind::Js => b"\n;\n",
+            CombineKind::Css => b"\n",
+        }
+    }
+}
+
+pub fn is_combine(path: &Path) -> bool {
+    path.iter().next().is_some_and(|v| v == NAMESPACE)
+}
+
+// 拆分 `combine/npm/a@1/x.js,npm/b@2/y.js` 为各个文件的路径
+fn split(path: &Path) -> Result<Vec<PathBuf>, FetchJSDelivrFailureError> {
+    let path = path
+        .strip_prefix(NAMESPACE)
+        .map_err(|_| FetchJSDelivrFailureError::InvalidPath("not a combine path"))?
+        .to_str()
+        .ok_or(FetchJSDelivrFailureError::PathCovert)?;
+    let parts: Vec<PathBuf> = path
+        .split(',')
+        .map(|v| PathBuf::from(v.trim_matches('/')))
+        .collect();
+    if parts.iter().any(|v| v.as_os_str().is_empty()) {
+        return Err(FetchJSDelivrFailureError::InvalidPath(
+            "combine contains an empty file",
+        ));
+    }
+    if parts.len() > MAX_PARTS {
+        return Err(FetchJSDelivrFailureError::InvalidPath(
+            "combine contains too many files",
+        ));
+    }
+    Ok(parts)
+}
+
+// 通过单个文件的缓存读取一个文件，path 为已解析为确切版本的路径
+async fn fetch_part(path: PathBuf) -> Result<Bytes, FetchJSDelivrFailureError> {
+    let resource = remember_jsdelivr_resource(path, &ForwardedQuery::default(), None).await?;
+    Ok(resource.data.into_bytes().await?)
+}
+
+// 在本地合并多个文件，如 /combine/npm/a@1/x.js,npm/b@2/y.js。
+// 每个文件经过各自的校验、版本解析与缓存，共享的依赖只回源一次；合并结果按解析后的路径另行缓存
+pub async fn remember_combined(
+    path: &Path,
+    encoding: Option<Encoding>,
+) -> Result<Resource, FetchJSDelivrFailureError> {
+    let parts = split(path)?;
+    let mut kind = None;
+    for part in &parts {
+        let part_str = part.to_string_lossy();
+        check_request(&part_str, part)?;
+        if is_combine(part) {
+            return Err(FetchJSDelivrFailureError::InvalidPath(
+                "combine cannot be nested",
+            ));
+        }
+        let part_kind =
+            CombineKind::from_path(part).ok_or(FetchJSDelivrFailureError::InvalidPath(
+                "combine only supports JavaScript and CSS files",
+            ))?;
+        if kind.get_or_insert(part_kind) != &part_kind {
+            return Err(FetchJSDelivrFailureError::InvalidPath(
+                "combine cannot mix JavaScript and CSS files",
+            ));
+        }
+    }
+    let kind = kind.ok_or(FetchJSDelivrFailureError::InvalidPath("combine is empty"))?;
+
+    // 先解析各个文件的版本，使 `@1` 与确切版本的请求共用同一份合并结果
+    let mut resolved = vec![];
+    for part in parts {
+        resolved.push(resolve::resolve_path(part).await?.path);
+    }
+    let combined_path = PathBuf::from(format!(
+        "{}/{}",
+        NAMESPACE,
+        resolved
+            .iter()
+            .map(|v| v.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(",")
+    ));
+    let key = format!(
+        "{}_combined",
+        cache_key(&combined_path, &ForwardedQuery::default())
+    );
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::read(&mut conn, &key, encoding).await {
+            Ok(Some((meta, data, encoding))) if meta.is_fresh() => {
+                cache::report_success();
+                stats::incr(&STATS.cache_hits);
+                let age = meta.age();
+                return Ok(Resource::new(meta.mime, data, CacheStatus::Hit, age)
+                    .with_encoding(encoding)
+                    .with_integrity(meta.integrity));
+            }
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    stats::incr(&STATS.cache_misses);
+
+    let files: Vec<Bytes> = stream::iter(resolved.into_iter().map(fetch_part))
+        .buffered(FETCH_CONCURRENCY)
+        .try_collect()
+        .await?;
+    let mut data = Vec::with_capacity(files.iter().map(|v| v.len() + 3).sum());
+    for (i, content) in files.iter().enumerate() {
+        if i > 0 {
+            data.extend_from_slice(kind.separator());
+        }
+        data.extend_from_slice(content);
+    }
+    let meta = EntryMeta::new(kind.mime().to_string(), CONFIG.cache.ttl as u64);
+    Ok(store_resource(
+        &key,
+        meta,
+        Bytes::from(data),
+        true,
+        CONFIG.cache.expire(),
+        encoding,
+    )
+    .await)
+}
@@ -0,0 +1,130 @@
+use super::package::PackageSpec;
+use super::types::FetchJSDelivrFailureError;
+use super::{build_client, PATH_SEGMENT};
+use crate::conf::jsdelivr::Upstream;
+use crate::utils::mime;
+use crate::CONFIG;
+use bytes::Bytes;
+use percent_encoding::utf8_percent_encode;
+use reqwest::{RequestBuilder, Url};
+use rocket::serde::json::Value;
+use semver::{Version, VersionReq};
+use std::path::Path;
+
+fn authorize(builder: RequestBuilder) -> RequestBuilder {
+    match &CONFIG.jsdelivr.github_token {
+        Some(token) if !token.is_empty() => {
+            builder.header("Authorization", format!("token {}", token))
+        }
+        _ => builder,
+    }
+}
+
+fn is_commit(v: &str) -> bool {
+    v.len() == 40 && v.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn enabled() -> bool {
+    CONFIG.jsdelivr.upstreams.contains(&Upstream::Github)
+}
+
+// 可以通过 GitHub 回源的路径：gh 仓库内的文件
+pub fn supports(path: &Path) -> bool {
+    PackageSpec::parse(path).is_some_and(|v| v.namespace == "gh" && !v.file.is_empty())
+}
+
+// 需要解析为 commit 的版本：分支或标签名。确切版本号与范围由数据 API 解析，commit 不解析
+pub fn is_ref(spec: &PackageSpec) -> bool {
+    match spec.version.as_deref() {
+        Some(v) => {
+            enabled()
+                && spec.namespace == "gh"
+                && v != "latest"
+                && !is_commit(v)
+                && Version::parse(v).is_err()
+                && VersionReq::parse(v).is_err()
+        }
+        None => false,
+    }
+}
+
+// 通过 `/repos/{owner}/{repo}/commits/{ref}/status` 将分支或标签解析为 commit，
+// GitHub 与 Gitea 均支持该接口
+pub async fn resolve_ref(spec: &PackageSpec) -> Result<Option<String>, FetchJSDelivrFailureError> {
+    let (owner, repo) = spec
+        .name
+        .split_once('/')
+        .ok_or(FetchJSDelivrFailureError::InvalidPath("invalid repository"))?;
+    let reference = spec.version.as_deref().unwrap_or("HEAD");
+    let mut url = Url::parse(&CONFIG.jsdelivr.github_api)?;
+    url.path_segments_mut()
+        .map_err(|_| FetchJSDelivrFailureError::InvalidPath("invalid GitHub API URL"))?
+        .pop_if_empty()
+        .extend(["repos", owner, repo, "commits", reference, "status"]);
+    let response = authorize(build_client()?.get(url))
+        .header("Accept", "application/json")
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(
+            status.as_u16(),
+        ));
+    }
+    let data: Value = response.json().await?;
+    Ok(data["sha"]
+        .as_str()
+        .filter(|v| is_commit(v))
+        .map(|v| v.to_string()))
+}
+
+// 按 github_raw 模板获取仓库中的文件，各字段按路径段编码，{path} 中的斜杠保留。
+// 未指定版本时使用默认分支（HEAD）
+pub async fn fetch_github(path: &Path) -> Result<(String, Bytes), FetchJSDelivrFailureError> {
+    let spec = PackageSpec::parse(path)
+        .filter(|v| v.namespace == "gh" && !v.file.is_empty())
+        .ok_or(FetchJSDelivrFailureError::InvalidPath(
+            "GitHub origin requires a file in a repository",
+        ))?;
+    let (owner, repo) = spec
+        .name
+        .split_once('/')
+        .ok_or(FetchJSDelivrFailureError::InvalidPath("invalid repository"))?;
+    let file = spec.file.trim_end_matches('/');
+    let url = CONFIG
+        .jsdelivr
+        .github_raw
+        .replace(
+            "{owner}",
+            &utf8_percent_encode(owner, PATH_SEGMENT).to_string(),
+        )
+        .replace(
+            "{repo}",
+            &utf8_percent_encode(repo, PATH_SEGMENT).to_string(),
+        )
+        .replace(
+            "{ref}",
+            &utf8_percent_encode(spec.version.as_deref().unwrap_or("HEAD"), PATH_SEGMENT)
+                .to_string(),
+        )
+        .replace(
+            "{path}",
+            &file
+                .split('/')
+                .map(|v| utf8_percent_encode(v, PATH_SEGMENT).to_string())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+    let response = authorize(build_client()?.get(Url::parse(&url)?))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(
+            status.as_u16(),
+        ));
+    }
+    // raw 地址通常对所有文件返回 text/plain，只按扩展名推断
+    let mime = mime::resolve(&format!("/{}", path.to_string_lossy()), None);
+    Ok((mime, response.bytes().await?))
+}
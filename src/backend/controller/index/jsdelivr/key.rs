@@ -0,0 +1,58 @@
+use super::query::ForwardedQuery;
+use crate::CONFIG;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+// GitHub 用户名与仓库名大小写不敏感，统一转为小写。
+// npm 包名不转换：早期的包名（如 JSONStream）区分大小写
+fn lowercase_name(segment: &str) -> String {
+    // 版本号（`@` 之后）保持原样，作用域包的 `@` 在开头
+    match segment.char_indices().skip(1).find(|(_, c)| *c == '@') {
+        Some((i, _)) => format!("{}{}", segment[..i].to_lowercase(), &segment[i..]),
+        None => segment.to_lowercase(),
+    }
+}
+
+// 规范化请求路径：合并重复的斜杠，处理 `.`/`..`，统一 gh 仓库名的大小写。
+// 路径已由 Rocket 解码，不再解码百分号编码；末尾的斜杠（目录）会保留为一个
+pub fn normalize(path: &str) -> String {
+    let mut segments: Vec<String> = vec![];
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            v => segments.push(v.to_string()),
+        }
+    }
+    if segments.first().is_some_and(|v| v == "gh") {
+        for segment in segments.iter_mut().take(3).skip(1) {
+            *segment = lowercase_name(segment);
+        }
+    }
+    let mut normalized = segments.join("/");
+    if path.ends_with('/') && !normalized.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+pub fn normalize_path(path: &Path) -> PathBuf {
+    PathBuf::from(normalize(&path.to_string_lossy()))
+}
+
+// 缓存键：`{prefix}:v{version}:{sha256(规范化的路径与查询参数)}`。
+// 修改 key_version 可以使所有旧缓存失效
+pub(crate) fn cache_key(path: &Path, query: &ForwardedQuery) -> String {
+    let mut raw = normalize(&path.to_string_lossy());
+    if let Some(v) = query.to_query_string() {
+        raw = format!("{}?{}", raw, v);
+    }
+    let hash: &[u8] = &Sha256::digest(raw.as_bytes());
+    crate::cache::namespaced(&format!(
+        "v{}:{}",
+        CONFIG.cache.key_version,
+        base16ct::lower::encode_string(hash)
+    ))
+}
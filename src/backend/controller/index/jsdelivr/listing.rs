@@ -0,0 +1,273 @@
+use super::key::{cache_key, normalize_path};
+use super::package::PackageSpec;
+use super::query::ForwardedQuery;
+use super::response::{store_resource, CacheStatus, Resource};
+use super::types::FetchJSDelivrFailureError;
+use super::PATH_SEGMENT;
+use crate::backend::controller::data::data_json;
+use crate::cache::entry::{self, EntryMeta};
+use crate::stats::{self, STATS};
+use crate::utils::compress::Encoding;
+use crate::{cache, CONFIG};
+use bytes::Bytes;
+use percent_encoding::utf8_percent_encode;
+use rocket::http::Accept;
+use rocket::serde::json::{serde_json, serde_json::json, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingFormat {
+    Json,
+    Html,
+}
+
+impl ListingFormat {
+    // 请求明确偏好 JSON 时返回 JSON，否则返回供浏览的 HTML 页面
+    pub fn from_accept(accept: Option<&Accept>) -> Self {
+        match accept {
+            Some(v) if v.preferred().media_type().is_json() => ListingFormat::Json,
+            _ => ListingFormat::Html,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListingFormat::Json => "json",
+            ListingFormat::Html => "html",
+        }
+    }
+
+    fn mime(&self) -> &'static str {
+        match self {
+            ListingFormat::Json => "application/json; charset=utf-8",
+            ListingFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+struct Listing {
+    package: String,
+    version: String,
+    path: String, // 目录路径，以斜杠开头和结尾
+    directories: BTreeSet<String>,
+    files: Vec<Value>, // { name, size, hash }
+}
+
+fn html_escape(v: &str) -> String {
+    v.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 链接中的文件名按路径段编码（`#`、`?`、`%`、空格等），再转义为 HTML 属性
+fn href(name: &str) -> String {
+    html_escape(&utf8_percent_encode(name, PATH_SEGMENT).to_string())
+}
+
+impl Listing {
+    // 从数据 API 的文件列表（structure=flat）中取出目录下的直接子项
+    fn build(spec: &PackageSpec, version: &str, tree: &Value) -> Option<Self> {
+        let dir = spec.file.trim_matches('/');
+        let prefix = if dir.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{}/", dir)
+        };
+        let mut listing = Listing {
+            package: spec.id(),
+            version: version.to_string(),
+            path: prefix.clone(),
+            directories: BTreeSet::new(),
+            files: vec![],
+        };
+        let mut found = false;
+        for file in tree["files"].as_array()? {
+            let name = match file["name"].as_str().and_then(|v| v.strip_prefix(&prefix)) {
+                Some(v) => v,
+                None => continue,
+            };
+            found = true;
+            match name.split_once('/') {
+                Some((directory, _)) => {
+                    listing.directories.insert(directory.to_string());
+                }
+                None => listing.files.push(json!({
+                    "name": name,
+                    "size": file["size"],
+                    "hash": file["hash"],
+                })),
+            }
+        }
+        (found || dir.is_empty()).then_some(listing)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "package": self.package,
+            "version": self.version,
+            "path": self.path,
+            "directories": self.directories,
+            "files": self.files,
+        })
+    }
+
+    fn to_html(&self) -> String {
+        let title = html_escape(&format!("{}@{}{}", self.package, self.version, self.path));
+        let mut rows = String::new();
+        if self.path != "/" {
+            rows.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+        }
+        for directory in &self.directories {
+            rows.push_str(&format!(
+                "<tr><td><a href=\"{}/\">{}/</a></td><td>-</td><td></td></tr>\n",
+                href(directory),
+                html_escape(directory)
+            ));
+        }
+        for file in &self.files {
+            let name = file["name"].as_str().unwrap_or_default();
+            rows.push_str(&format!(
+                "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td><code>{}</code></td></tr>\n",
+                href(name),
+                html_escape(name),
+                file["size"]
+                    .as_u64()
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "-".into()),
+                html_escape(file["hash"].as_str().unwrap_or_default())
+            ));
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>SHA-256</th></tr>\n{1}</table>\n</body>\n</html>\n",
+            title, rows
+        )
+    }
+
+    fn render(&self, format: ListingFormat) -> Bytes {
+        match format {
+            ListingFormat::Json => Bytes::from(
+                serde_json::to_vec(&self.to_json()).expect("Listing is always serializable"),
+            ),
+            ListingFormat::Html => Bytes::from(self.to_html()),
+        }
+    }
+}
+
+// 从数据 API 读取包某个版本的全部文件（structure=flat），文件名以斜杠开头
+pub(crate) async fn package_tree(
+    spec: &PackageSpec,
+    version: &str,
+) -> Result<Value, FetchJSDelivrFailureError> {
+    data_json(
+        Path::new(&format!("v1/packages/{}@{}", spec.id(), version)),
+        &[("structure".into(), "flat".into())],
+    )
+    .await
+}
+
+// 返回包内目录的文件列表，path 为已解析为确切版本的目录路径。列表与资源一样缓存
+pub async fn remember_listing(
+    path: &Path,
+    format: ListingFormat,
+    encoding: Option<Encoding>,
+) -> Result<Resource, FetchJSDelivrFailureError> {
+    let spec = PackageSpec::parse(&normalize_path(path)).ok_or(
+        FetchJSDelivrFailureError::InvalidPath("directory listing requires a package"),
+    )?;
+    let version = spec
+        .version
+        .clone()
+        .ok_or(FetchJSDelivrFailureError::InvalidPath(
+            "directory listing requires a version",
+        ))?;
+    let key = format!(
+        "{}_listing_{}",
+        cache_key(path, &ForwardedQuery::default()),
+        format.as_str()
+    );
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::read(&mut conn, &key, encoding).await {
+            Ok(Some((meta, data, encoding))) if meta.is_fresh() => {
+                cache::report_success();
+                stats::incr(&STATS.cache_hits);
+                let age = meta.age();
+                return Ok(
+                    Resource::new(meta.mime, data, CacheStatus::Hit, age).with_encoding(encoding)
+                );
+            }
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    stats::incr(&STATS.cache_misses);
+    let tree = package_tree(&spec, &version).await?;
+    let listing = Listing::build(&spec, &version, &tree)
+        .ok_or(FetchJSDelivrFailureError::RequestStatusCheck(404))?;
+    let meta = EntryMeta::new(format.mime().to_string(), CONFIG.cache.ttl as u64);
+    let data = listing.render(format);
+    Ok(store_resource(&key, meta, data, false, CONFIG.cache.expire(), encoding).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Value {
+        json!({
+            "files": [
+                { "name": "/package.json", "size": 512, "hash": "a" },
+                { "name": "/README.md", "hash": "b" },
+                { "name": "/dist/a b#1.js", "size": 10, "hash": "c" },
+                { "name": "/dist/esm/index.js", "size": 20, "hash": "d" },
+                { "name": "/dist/esm/util/x.js", "size": 30, "hash": "e" },
+            ]
+        })
+    }
+
+    fn build(path: &str) -> Option<Listing> {
+        let spec = PackageSpec::parse(Path::new(path)).unwrap();
+        Listing::build(&spec, "1.0.0", &tree())
+    }
+
+    #[test]
+    fn lists_the_package_root() {
+        let listing = build("npm/pkg@1.0.0/").unwrap();
+        assert_eq!(listing.path, "/");
+        assert_eq!(listing.directories.iter().collect::<Vec<_>>(), ["dist"]);
+        let files: Vec<&str> = listing
+            .files
+            .iter()
+            .map(|v| v["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(files, ["package.json", "README.md"]);
+    }
+
+    #[test]
+    fn lists_only_direct_children_of_nested_directories() {
+        let listing = build("npm/pkg@1.0.0/dist/").unwrap();
+        assert_eq!(listing.path, "/dist/");
+        assert_eq!(listing.directories.iter().collect::<Vec<_>>(), ["esm"]);
+        assert_eq!(listing.files.len(), 1);
+        let listing = build("npm/pkg@1.0.0/dist/esm").unwrap();
+        assert_eq!(listing.directories.iter().collect::<Vec<_>>(), ["util"]);
+        assert_eq!(listing.files[0]["name"], "index.js");
+    }
+
+    #[test]
+    fn missing_directory_is_not_found() {
+        assert!(build("npm/pkg@1.0.0/lib/").is_none());
+        // 前缀相同但不是该目录下的文件
+        assert!(build("npm/pkg@1.0.0/dis/").is_none());
+    }
+
+    #[test]
+    fn html_links_are_encoded() {
+        let html = build("npm/pkg@1.0.0/dist/").unwrap().to_html();
+        assert!(html.contains("<a href=\"a%20b%231.js\">a b#1.js</a>"));
+        let html = build("npm/pkg@1.0.0/").unwrap().to_html();
+        assert!(html.contains(">README.md</a></td><td>-</td>"));
+        assert!(!html.contains("null"));
+    }
+}
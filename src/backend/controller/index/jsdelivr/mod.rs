@@ -1,36 +1,83 @@
+pub mod combine;
+pub mod github;
+pub mod key;
+pub mod listing;
+pub mod npm;
+pub mod package;
+pub mod query;
+pub mod resolve;
+pub mod response;
+pub mod revalidate;
+pub mod rules;
 pub mod types;
+pub mod validate;
 use bytes::Bytes;
-use deadpool_redis::{redis::AsyncCommands, Connection};
+use deadpool_redis::Connection;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use reqwest::{Client, Url};
-use rocket::{get, http::ContentType, serde::json::Value, Responder};
-use sha2::{Digest, Sha256};
-use std::{
-    path::{Path, PathBuf},
-    str::FromStr,
+use rocket::{
+    get, head,
+    http::{uri::Origin, Accept},
+    serde::json::Value,
+    Responder,
 };
-use tracing::{error, instrument};
+use std::path::{Path, PathBuf};
+use tracing::{error, instrument, warn};
 
-use crate::utils::response::{fail, fail_with_message, APIResponse};
+use crate::auth::guard::AssetAccess;
+use crate::cache::chunk::Body;
+use crate::cache::entry::{self, EntryMeta};
+use crate::conf::jsdelivr::Upstream;
+use crate::stats::{self, STATS};
+use crate::utils::compress::{AcceptEncoding, Encoding};
+use crate::utils::hash::Integrity;
+use crate::utils::mime;
+use crate::utils::response::{fail_with_code, APIResponse};
 use crate::{cache, CONFIG};
 
+use self::key::cache_key;
+use self::listing::ListingFormat;
+use self::query::ForwardedQuery;
+use self::response::{store_resource, CacheStatus, Redirect, Resource};
 use self::types::FetchJSDelivrFailureError;
 
+// 会被负缓存的上游状态码
+const NEGATIVE_CACHE_STATUS: [u16; 3] = [403, 404, 410];
+// 重定向地址中路径段需要编码的字符
+pub(crate) const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
 #[derive(Responder)]
 pub enum JSDelivrResponse {
     Json(APIResponse<Value>),
-    Raw(Box<(ContentType, Vec<u8>)>),
+    Raw(Box<Resource>),
+    Redirect(Redirect),
 }
 
-fn convert_url(base: &str, path: PathBuf) -> Result<Url, types::FetchJSDelivrFailureError> {
+fn convert_url(
+    base: &str,
+    path: PathBuf,
+    query: &ForwardedQuery,
+) -> Result<Url, types::FetchJSDelivrFailureError> {
     let mut url = Url::parse(base)?;
     let mut path = match path.into_os_string().into_string() {
         Ok(v) => v,
         Err(_) => return Err(types::FetchJSDelivrFailureError::PathCovert),
     };
-    let raw_path = url.path();
+    let raw_path = url.path().to_string();
     if raw_path != "/" {
         path = match Path::new("/")
-            .join(raw_path)
+            .join(&raw_path)
             .join(path)
             .into_os_string()
             .into_string()
@@ -40,24 +87,40 @@ fn convert_url(base: &str, path: PathBuf) -> Result<Url, types::FetchJSDelivrFai
         };
     }
     url.set_path(path.as_str());
+    // 拼接并由 Url 处理 `..` 之后的路径必须仍在镜像的路径之下
+    if !url
+        .path()
+        .starts_with(&format!("{}/", raw_path.trim_end_matches('/')))
+    {
+        return Err(types::FetchJSDelivrFailureError::InvalidPath(
+            "path escapes the mirror base path",
+        ));
+    }
+    url.set_query(query.to_query_string().as_deref());
     Ok(url)
 }
 
-async fn fetch_jsdelivr(
-    path: PathBuf,
-) -> Result<(String, Bytes), types::FetchJSDelivrFailureError> {
-    let client = Client::builder()
+pub(crate) fn build_client() -> Result<Client, reqwest::Error> {
+    Client::builder()
         .user_agent(match &CONFIG.jsdelivr.user_agent {
             Some(v) => v,
             None => concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
         })
-        .build()?;
+        .build()
+}
+
+async fn fetch_mirror(
+    path: PathBuf,
+    query: &ForwardedQuery,
+) -> Result<(String, Bytes), types::FetchJSDelivrFailureError> {
+    let client = build_client()?;
     let mirror = match &CONFIG.jsdelivr.mirror {
         Some(v) => v,
         None => "https://cdn.jsdelivr.net",
     };
+    let path_str = format!("/{}", path.to_string_lossy());
     let response = client
-        .get(convert_url(mirror, path)?)
+        .get(convert_url(mirror, path, query)?)
         .header(
             "Referer",
             match &CONFIG.jsdelivr.referer {
@@ -74,54 +137,393 @@ async fn fetch_jsdelivr(
         ));
     }
     // 由于只使用 GET 方法获取 JSDelivr CDN 的资源，因此 Content-Type 应该就是 Mime
-    let mime: String = if let Some(value) = response.headers().get(reqwest::header::CONTENT_TYPE) {
-        value.to_str()?.to_string()
-    } else {
-        "text/plain".to_string()
+    // 上游未返回或无法解析时按扩展名推断
+    let upstream_mime = match response.headers().get(reqwest::header::CONTENT_TYPE) {
+        Some(value) => Some(value.to_str()?),
+        None => None,
     };
+    let mime = mime::resolve(&path_str, upstream_mime);
     Ok((mime, response.bytes().await?))
 }
 
-async fn remember_jsdelivr_resource(
+// 按 jsdelivr.upstreams 的顺序回源，上游故障或拒绝时尝试下一个，上游明确的结果（如 404）直接返回。
+// 回源使用校验后的请求路径，规范化的路径只用于缓存键（npm 的旧包名区分大小写）
+async fn fetch_jsdelivr(
+    path: PathBuf,
+    query: &ForwardedQuery,
+) -> Result<(String, Bytes), types::FetchJSDelivrFailureError> {
+    let mut last_error = None;
+    for upstream in &CONFIG.jsdelivr.upstreams {
+        let result = match upstream {
+            Upstream::Mirror => fetch_mirror(path.clone(), query).await,
+            // tarball 中的文件不受查询参数影响，带参数的请求只能通过镜像获取
+            Upstream::Npm if query.is_empty() && npm::supports(&path) => {
+                npm::fetch_npm(&path).await
+            }
+            Upstream::Github if github::supports(&path) => github::fetch_github(&path).await,
+            Upstream::Npm | Upstream::Github => continue,
+        };
+        match result {
+            // jsDelivr 对过大的包与仓库返回 403，此时同样尝试下一个回源方式
+            Err(e)
+                if e.is_upstream_failure()
+                    || matches!(e, FetchJSDelivrFailureError::RequestStatusCheck(403)) =>
+            {
+                warn!(
+                    "Upstream {:?} failed for {}: {}",
+                    upstream,
+                    path.display(),
+                    e
+                );
+                last_error = Some(e);
+            }
+            v => return v,
+        }
+    }
+    // 没有可用于该路径的回源方式
+    Err(last_error.unwrap_or(types::FetchJSDelivrFailureError::RequestStatusCheck(404)))
+}
+
+// 缓存上游明确的失败结果，避免不存在的路径反复回源
+async fn remember_negative(key: &str, status: u16) {
+    if CONFIG.cache.negative_ttl == 0 {
+        return;
+    }
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::write_negative(&mut conn, key, status, CONFIG.cache.negative_ttl).await {
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+}
+
+// 从上游获取资源并写入缓存（覆盖已有缓存）。缓存不可用时仅记录错误，不影响返回
+// encoding 为客户端可接受的压缩编码
+pub(crate) async fn refresh_jsdelivr_resource(
+    path: PathBuf,
+    query: &ForwardedQuery,
+    encoding: Option<Encoding>,
+) -> Result<Resource, FetchJSDelivrFailureError> {
+    let key = cache_key(&path, query);
+    stats::incr(&STATS.upstream_fetches);
+    let (mime, data) = match fetch_jsdelivr(path, query).await {
+        Ok(v) => v,
+        Err(FetchJSDelivrFailureError::RequestStatusCheck(status))
+            if NEGATIVE_CACHE_STATUS.contains(&status) =>
+        {
+            remember_negative(&key, status).await;
+            return Err(FetchJSDelivrFailureError::RequestStatusCheck(status));
+        }
+        Err(e) => {
+            stats::incr(&STATS.upstream_errors);
+            return Err(e);
+        }
+    };
+    // 与预先压缩的版本一同保存到 Redis，过期后额外保留一段时间供后台刷新与上游故障时使用
+    let meta = EntryMeta::new(mime, CONFIG.cache.ttl as u64);
+    Ok(store_resource(&key, meta, data, true, CONFIG.cache.expire(), encoding).await)
+}
+
+// 资源的 SRI 摘要。旧版本写入的缓存中没有摘要，此时在阻塞任务中计算一次并写回元数据
+pub(crate) async fn resource_integrity(
+    path: &Path,
+    query: &ForwardedQuery,
+    resource: Resource,
+) -> Result<Integrity, FetchJSDelivrFailureError> {
+    if let Some(v) = resource.integrity {
+        return Ok(v);
+    }
+    let data = resource.data.into_bytes().await?;
+    let integrity = tokio::task::spawn_blocking(move || Integrity::compute(&data))
+        .await
+        .map_err(std::io::Error::other)?;
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::write_integrity(&mut conn, &cache_key(path, query), &integrity).await {
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    Ok(integrity)
+}
+
+pub(crate) async fn remember_jsdelivr_resource(
     path: PathBuf,
-) -> Result<(String, Bytes), FetchJSDelivrFailureError> {
-    let key: &[u8] = &Sha256::digest(path.to_string_lossy().to_string().as_bytes());
-    let key: String = base16ct::lower::encode_string(key);
+    query: &ForwardedQuery,
+    encoding: Option<Encoding>,
+) -> Result<Resource, FetchJSDelivrFailureError> {
+    let key = cache_key(&path, query);
+    let mut stale: Option<(EntryMeta, Body, Option<Encoding>)> = None;
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::read(&mut conn, &key, encoding).await {
+            Ok(Some((meta, data, encoding))) => {
+                cache::report_success();
+                let age = meta.age();
+                if meta.is_fresh() {
+                    stats::incr(&STATS.cache_hits);
+                    let remaining = meta.remaining();
+                    if remaining < CONFIG.cache.refresh_ahead
+                        && revalidate::is_hot(&key, remaining).await
+                    {
+                        revalidate::spawn_refresh(key, path, query.clone());
+                    }
+                    return Ok(Resource::new(meta.mime, data, CacheStatus::Hit, age)
+                        .with_encoding(encoding)
+                        .with_integrity(meta.integrity));
+                }
+                if age < meta.ttl + CONFIG.cache.stale_while_revalidate as u64 {
+                    // 先返回过期内容，再在后台刷新
+                    stats::incr(&STATS.cache_hits);
+                    revalidate::spawn_refresh(key, path, query.clone());
+                    return Ok(
+                        Resource::new(meta.mime, data, CacheStatus::Revalidating, age)
+                            .with_encoding(encoding)
+                            .with_integrity(meta.integrity),
+                    );
+                }
+                stale = Some((meta, data, encoding));
+            }
+            Ok(None) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+        match entry::read_negative(&mut conn, &key).await {
+            Ok(Some(status)) => {
+                stats::incr(&STATS.negative_hits);
+                return Err(FetchJSDelivrFailureError::RequestStatusCheck(status));
+            }
+            Ok(None) => {}
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    stats::incr(&STATS.cache_misses);
+    match (
+        refresh_jsdelivr_resource(path, query, encoding).await,
+        stale,
+    ) {
+        (Err(e), Some((meta, data, encoding))) if e.is_upstream_failure() => {
+            warn!("Upstream failed, serving stale content: {}", e);
+            stats::incr(&STATS.stale_served);
+            let age = meta.age();
+            Ok(Resource::new(meta.mime, data, CacheStatus::Stale, age)
+                .with_encoding(encoding)
+                .with_integrity(meta.integrity))
+        }
+        (v, _) => v,
+    }
+}
+
+// 仅根据缓存的元数据构造资源，不读取内容。元数据不新鲜或缺少大小信息时返回 None
+async fn peek_jsdelivr_resource(
+    path: &Path,
+    query: &ForwardedQuery,
+    encoding: Option<Encoding>,
+) -> Option<Resource> {
+    let key = cache_key(path, query);
+    let mut conn = cache::try_get_connection().await?;
+    let meta = match entry::read_meta(&mut conn, &key).await {
+        Ok(v) => {
+            cache::report_success();
+            v?
+        }
+        Err(e) => {
+            cache::report_error(&e);
+            return None;
+        }
+    };
+    if !meta.is_fresh() {
+        return None;
+    }
+    let encoding = encoding.filter(|v| meta.encodings.contains(v));
+    let size = meta.size_of(encoding)?;
+    stats::incr(&STATS.cache_hits);
+    let age = meta.age();
+    Some(
+        Resource::new(meta.mime, Body::SizeOnly(size), CacheStatus::Hit, age)
+            .with_encoding(encoding)
+            .with_integrity(meta.integrity),
+    )
+}
 
+// 删除缓存，返回被删除的键数量
+pub(crate) async fn forget_jsdelivr_resource(
+    path: PathBuf,
+    query: &ForwardedQuery,
+) -> Result<usize, FetchJSDelivrFailureError> {
+    let key = cache_key(&path, query);
     let conn: &mut Connection = &mut (cache::get_connection().await?);
-    let mime: Option<String> = conn.get(format!("{}_mime", key)).await?;
-    let data: Option<Bytes> = conn.get(format!("{}_data", key)).await?;
-    if let (Some(mime), Some(data)) = (mime, data) {
-        return Ok((mime, data));
-    }
-    let (mime, data) = fetch_jsdelivr(path).await?;
-    // 保存到 Redis
-    conn.set_ex(format!("{}_mime", key), mime.clone(), 60 * 60 * 2)
-        .await?;
-    conn.set_ex(format!("{}_data", key), data.to_vec(), 60 * 60 * 2)
-        .await?;
-    Ok((mime, data))
+    Ok(entry::delete(conn, &key).await?)
+}
+
+// 校验请求路径并检查访问规则，raw 为原始请求路径
+pub(crate) fn check_request(raw: &str, path: &Path) -> Result<(), FetchJSDelivrFailureError> {
+    validate::validate(raw, path)?;
+    rules::check(path)
+}
+
+pub(crate) fn error_response(e: &FetchJSDelivrFailureError) -> APIResponse<Value> {
+    let message = match e {
+        types::FetchJSDelivrFailureError::RequestStatusCheck(_) => "".into(),
+        _ => e.to_string(),
+    };
+    fail_with_code(e.status() as i64, Some(e.code()), None, message)
+}
+
+// 重定向的目标地址：对路径段重新编码，保留目录末尾的斜杠与原始的查询字符串
+pub(crate) fn redirect_location(path: &Path, directory: bool, query: Option<&str>) -> String {
+    let mut location = String::new();
+    for segment in path.iter() {
+        location.push('/');
+        location.extend(utf8_percent_encode(
+            &segment.to_string_lossy(),
+            PATH_SEGMENT,
+        ));
+    }
+    if directory {
+        location.push('/');
+    }
+    match query {
+        Some(v) if !v.is_empty() => format!("{}?{}", location, v),
+        _ => location,
+    }
+}
+
+// 处理资源请求：校验路径，将版本解析为确切版本后读取缓存或回源。
+// 命名空间开启了重定向时，未固定版本的请求重定向到确切版本的地址；
+// 以斜杠结尾的目录路径返回文件列表；combine 在本地合并；head 为 true 时优先只读取缓存的元数据
+async fn serve_jsdelivr_resource(
+    uri: &Origin<'_>,
+    media: Option<&Accept>,
+    path: PathBuf,
+    query: &ForwardedQuery,
+    encoding: Option<Encoding>,
+    head: bool,
+) -> Result<JSDelivrResponse, FetchJSDelivrFailureError> {
+    check_request(uri.path().as_str(), &path)?;
+    if combine::is_combine(&path) {
+        let resource = combine::remember_combined(&path, encoding).await?;
+        return Ok(JSDelivrResponse::Raw(Box::new(resource)));
+    }
+    let directory = uri.path().as_str().ends_with('/');
+    let resolved = resolve::resolve_path(path).await?;
+    let namespace = resolved
+        .path
+        .iter()
+        .next()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if resolved.resolved && CONFIG.resolve.redirect.contains(&namespace) {
+        return Ok(JSDelivrResponse::Redirect(Redirect {
+            location: redirect_location(&resolved.path, directory, uri.query().map(|v| v.as_str())),
+            max_age: CONFIG.resolve.redirect_max_age,
+        }));
+    }
+    if directory {
+        let format = ListingFormat::from_accept(media);
+        let resource = listing::remember_listing(&resolved.path, format, encoding).await?;
+        return Ok(JSDelivrResponse::Raw(Box::new(
+            resource.with_version(resolved.version).with_vary_accept(),
+        )));
+    }
+    let peeked = if head {
+        peek_jsdelivr_resource(&resolved.path, query, encoding).await
+    } else {
+        None
+    };
+    let resource = match peeked {
+        Some(v) => v,
+        None => remember_jsdelivr_resource(resolved.path, query, encoding).await?,
+    };
+    Ok(JSDelivrResponse::Raw(Box::new(
+        resource.with_version(resolved.version),
+    )))
 }
 
 #[get("/<path..>")]
-#[instrument]
-pub async fn get(path: PathBuf) -> JSDelivrResponse {
-    match remember_jsdelivr_resource(path).await {
-        Ok((mime, data)) => {
-            let content_type = ContentType::from_str(mime.as_str()).unwrap_or(ContentType::Plain);
-            JSDelivrResponse::Raw(Box::new((content_type, data.to_vec())))
+#[instrument(skip(_access, accept, uri, media))]
+pub async fn get(
+    _access: AssetAccess,
+    accept: AcceptEncoding,
+    query: ForwardedQuery,
+    uri: &Origin<'_>,
+    media: Option<&Accept>,
+    path: PathBuf,
+) -> JSDelivrResponse {
+    match serve_jsdelivr_resource(uri, media, path, &query, accept.0, false).await {
+        Ok(v) => v,
+        Err(ref e) => {
+            error!("{:?}", e);
+            JSDelivrResponse::Json(error_response(e))
         }
+    }
+}
+
+// HEAD 请求优先只读取缓存的元数据，无法确定时按 GET 处理，由 Rocket 去除响应体
+#[head("/<path..>")]
+#[instrument(skip(_access, accept, uri, media))]
+pub async fn head(
+    _access: AssetAccess,
+    accept: AcceptEncoding,
+    query: ForwardedQuery,
+    uri: &Origin<'_>,
+    media: Option<&Accept>,
+    path: PathBuf,
+) -> JSDelivrResponse {
+    match serve_jsdelivr_resource(uri, media, path, &query, accept.0, true).await {
+        Ok(v) => v,
         Err(ref e) => {
             error!("{:?}", e);
-            match e {
-                types::FetchJSDelivrFailureError::ReqwestOperation(_) => {
-                    JSDelivrResponse::Json(fail_with_message(500, None, e.to_string()))
-                }
-                types::FetchJSDelivrFailureError::RequestStatusCheck(status) => {
-                    JSDelivrResponse::Json(fail(*status as i64, None))
-                }
-                _ => JSDelivrResponse::Json(fail_with_message(400, None, e.to_string())),
-            }
+            JSDelivrResponse::Json(error_response(e))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror_url(base: &str, path: &str) -> Result<String, FetchJSDelivrFailureError> {
+        convert_url(base, PathBuf::from(path), &ForwardedQuery::default()).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn convert_url_without_base_path() {
+        assert_eq!(
+            mirror_url(
+                "https://cdn.jsdelivr.net",
+                "npm/jquery@3.7.1/dist/jquery.js"
+            )
+            .unwrap(),
+            "https://cdn.jsdelivr.net/npm/jquery@3.7.1/dist/jquery.js"
+        );
+        assert_eq!(
+            mirror_url("https://cdn.jsdelivr.net/", "gh/user/repo@main/a.js").unwrap(),
+            "https://cdn.jsdelivr.net/gh/user/repo@main/a.js"
+        );
+    }
+
+    #[test]
+    fn convert_url_with_base_path() {
+        assert_eq!(
+            mirror_url("https://mirror.example.com/jsdelivr", "npm/vue@3/index.js").unwrap(),
+            "https://mirror.example.com/jsdelivr/npm/vue@3/index.js"
+        );
+        assert_eq!(
+            mirror_url("https://mirror.example.com/jsdelivr/", "npm/vue@3/index.js").unwrap(),
+            "https://mirror.example.com/jsdelivr/npm/vue@3/index.js"
+        );
+    }
+
+    #[test]
+    fn convert_url_rejects_escaping_base_path() {
+        assert!(matches!(
+            mirror_url("https://mirror.example.com/jsdelivr", "../secret"),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            mirror_url(
+                "https://mirror.example.com/jsdelivr",
+                "../jsdelivr-private/a.js"
+            ),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+    }
+}
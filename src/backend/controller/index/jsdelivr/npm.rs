@@ -0,0 +1,473 @@
+use super::build_client;
+use super::package::PackageSpec;
+use super::resolve::{match_npm_version, npm_packument};
+use super::types::FetchJSDelivrFailureError;
+use crate::cache::entry::{self, EntryMeta};
+use crate::utils::mime;
+use crate::{cache, CONFIG};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use deadpool_redis::redis::{AsyncCommands, RedisError};
+use flate2::read::GzDecoder;
+use reqwest::{RequestBuilder, Url};
+use rocket::serde::json::serde_json;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tracing::{debug, warn};
+
+lazy_static! {
+    // 正在下载的包，同一个包的 tarball 同时只下载并解压一次
+    static ref DOWNLOADING: Mutex<HashMap<String, Arc<AsyncMutex<()>>>> =
+        Mutex::new(HashMap::new());
+}
+
+// 持有期间同一个包的其他请求等待（直到文件写入缓存），释放时清理没有其他请求等待的锁
+struct DownloadGuard {
+    key: String,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for DownloadGuard {
+    fn drop(&mut self) {
+        let mut downloading = DOWNLOADING.lock().unwrap();
+        // 只剩列表与当前持有者的引用
+        if downloading
+            .get(&self.key)
+            .is_some_and(|v| Arc::strong_count(v) <= 2)
+        {
+            downloading.remove(&self.key);
+        }
+    }
+}
+
+async fn lock_package(package_key: &str) -> DownloadGuard {
+    let lock = DOWNLOADING
+        .lock()
+        .unwrap()
+        .entry(package_key.to_string())
+        .or_default()
+        .clone();
+    DownloadGuard {
+        key: package_key.to_string(),
+        _guard: lock.lock_owned().await,
+    }
+}
+
+// 地址是否与配置的 registry 同源（协议、主机与端口均相同）
+fn is_registry(url: &str) -> bool {
+    match (Url::parse(url), Url::parse(&CONFIG.jsdelivr.npm_registry)) {
+        (Ok(url), Ok(registry)) => {
+            url.scheme() == registry.scheme()
+                && url.host_str() == registry.host_str()
+                && url.port_or_known_default() == registry.port_or_known_default()
+        }
+        _ => false,
+    }
+}
+
+// 只向配置的 registry 发送 Token，tarball 可能位于其他域名
+pub(crate) fn authorize(builder: RequestBuilder, url: &str) -> RequestBuilder {
+    match &CONFIG.jsdelivr.npm_token {
+        Some(token) if !token.is_empty() && is_registry(url) => builder.bearer_auth(token),
+        _ => builder,
+    }
+}
+
+// 可以通过 tarball 回源的路径：npm 包内的文件
+pub fn supports(path: &Path) -> bool {
+    PackageSpec::parse(path).is_some_and(|v| v.namespace == "npm" && !v.file.is_empty())
+}
+
+// `{prefix}:npm:v{version}:{name}@{version}` 中保存解压后的文件列表，
+// 每个文件按 entry 模块的格式保存在 `{列表的键}:{sha256(文件路径)}` 中
+fn package_key(name: &str, version: &str) -> String {
+    cache::namespaced(&format!(
+        "npm:v{}:{}@{}",
+        CONFIG.cache.key_version, name, version
+    ))
+}
+
+fn file_key(package_key: &str, file: &str) -> String {
+    let hash: &[u8] = &Sha256::digest(file.as_bytes());
+    format!("{}:{}", package_key, base16ct::lower::encode_string(hash))
+}
+
+fn parse_octal(field: &[u8]) -> io::Result<usize> {
+    let value = String::from_utf8_lossy(field);
+    let value = value.trim_matches(|c: char| c == '\0' || c == ' ');
+    if value.is_empty() {
+        return Ok(0);
+    }
+    usize::from_str_radix(value, 8)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid tar header"))
+}
+
+fn c_str(field: &[u8]) -> String {
+    let end = field.iter().position(|v| *v == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+// pax 扩展头中的 `path` 记录，格式为 `{长度} path={路径}\n`
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .find_map(|v| v.split_once(' ')?.1.strip_prefix("path="))
+        .map(|v| v.to_string())
+}
+
+// 解压 tarball，返回包内的文件（去掉第一层目录，通常为 `package/`），
+// 文件内容均为解压结果的切片，不逐个复制
+fn unpack(data: &[u8], max_size: usize) -> io::Result<Vec<(String, Bytes)>> {
+    let mut tar = vec![];
+    GzDecoder::new(data)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut tar)?;
+    if tar.len() > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "tarball exceeds the size limit",
+        ));
+    }
+    let tar = Bytes::from(tar);
+    let mut files = vec![];
+    let mut long_name: Option<String> = None;
+    let mut offset = 0;
+    while offset + 512 <= tar.len() {
+        let header = &tar[offset..offset + 512];
+        if header.iter().all(|v| *v == 0) {
+            break;
+        }
+        let size = parse_octal(&header[124..136])?;
+        let start = offset + 512;
+        let end = start
+            .checked_add(size)
+            .filter(|v| *v <= tar.len())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tarball"))?;
+        let body = tar.slice(start..end);
+        offset = start + size.div_ceil(512) * 512;
+        match header[156] {
+            b'L' => long_name = Some(c_str(&body)),
+            b'x' => long_name = pax_path(&body).or(long_name),
+            b'0' | 0 => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let prefix = c_str(&header[345..500]);
+                    let name = c_str(&header[..100]);
+                    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                        format!("{}/{}", prefix, name)
+                    } else {
+                        name
+                    }
+                });
+                let file = match name.trim_start_matches("./").split_once('/') {
+                    Some((_, v)) => v.to_string(),
+                    None => continue,
+                };
+                if file.is_empty() || file.split('/').any(|v| v.is_empty() || v == "..") {
+                    continue;
+                }
+                files.push((file, body));
+            }
+            _ => long_name = None,
+        }
+    }
+    Ok(files)
+}
+
+// 校验 tarball 的 sha512 摘要（registry 返回的 dist.integrity）
+fn verify(data: &[u8], integrity: Option<&str>) -> bool {
+    match integrity.and_then(|v| v.strip_prefix("sha512-")) {
+        Some(expected) => STANDARD.encode(Sha512::digest(data)) == expected,
+        None => true,
+    }
+}
+
+// 读取已解压的文件：None 表示尚未解压（或缓存不可用），Some(None) 表示包内没有该文件
+async fn read_file(package_key: &str, file: &str) -> Option<Option<(String, Bytes)>> {
+    let mut conn = cache::try_get_connection().await?;
+    let files: Result<Option<String>, RedisError> = conn.get(package_key).await;
+    let files: Vec<String> = match files {
+        Ok(v) => {
+            cache::report_success();
+            serde_json::from_str(&v?).ok()?
+        }
+        Err(e) => {
+            cache::report_error(&e);
+            return None;
+        }
+    };
+    if !files.iter().any(|v| v == file) {
+        return Some(None);
+    }
+    match entry::read(&mut conn, &file_key(package_key, file), None).await {
+        Ok(Some((meta, data, _))) => match data.into_bytes().await {
+            Ok(data) => Some(Some((meta.mime, data))),
+            Err(e) => {
+                warn!("Failed to read unpacked file {}: {}", file, e);
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            cache::report_error(&e);
+            None
+        }
+    }
+}
+
+// 将解压后的文件写入缓存，最后写入文件列表，列表存在时所有文件均已写入
+async fn store_files(package_key: String, base: String, files: Vec<(String, Bytes)>) {
+    let mut conn = match cache::try_get_connection().await {
+        Some(v) => v,
+        None => return,
+    };
+    let expire = CONFIG.cache.expire();
+    for (file, data) in &files {
+        let mime = mime::resolve(&format!("{}{}", base, file), None);
+        let meta = EntryMeta::new(mime, CONFIG.cache.ttl as u64);
+        if let Err(e) = entry::write(
+            &mut conn,
+            &file_key(&package_key, file),
+            &meta,
+            data,
+            &[],
+            expire,
+        )
+        .await
+        {
+            cache::report_error(&e);
+            return;
+        }
+    }
+    let names: Vec<&String> = files.iter().map(|(v, _)| v).collect();
+    let names = serde_json::to_string(&names).expect("file list is always serializable");
+    let result: Result<(), RedisError> = conn.set_ex(&package_key, names, expire).await;
+    match result {
+        Ok(_) => cache::report_success(),
+        Err(e) => cache::report_error(&e),
+    }
+}
+
+// 已解压的文件：None 表示需要下载 tarball
+async fn read_cached(
+    package_key: &str,
+    file: &str,
+) -> Option<Result<(String, Bytes), FetchJSDelivrFailureError>> {
+    read_file(package_key, file)
+        .await
+        .map(|v| v.ok_or(FetchJSDelivrFailureError::RequestStatusCheck(404)))
+}
+
+// 下载压缩的 tarball，压缩后的大小同样不超过解压后的上限
+async fn download(mut response: reqwest::Response) -> Result<Vec<u8>, FetchJSDelivrFailureError> {
+    let max_size = CONFIG.jsdelivr.npm_tarball_max_size;
+    let too_large =
+        || FetchJSDelivrFailureError::InvalidTarball("tarball exceeds the size limit".into());
+    if response
+        .content_length()
+        .is_some_and(|v| v > max_size as u64)
+    {
+        return Err(too_large());
+    }
+    let mut data = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if data.len() + chunk.len() > max_size {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+// 从 npm registry 的 tarball 中读取文件。tarball 只下载并解压一次，
+// 包内的所有文件都会写入缓存，之后的请求直接从缓存读取
+pub async fn fetch_npm(path: &Path) -> Result<(String, Bytes), FetchJSDelivrFailureError> {
+    let spec = PackageSpec::parse(path)
+        .filter(|v| v.namespace == "npm" && !v.file.is_empty())
+        .ok_or(FetchJSDelivrFailureError::InvalidPath(
+            "npm origin requires a file in a package",
+        ))?;
+    let file = spec.file.trim_end_matches('/');
+    let specifier = spec.version.as_deref().unwrap_or("latest");
+    let mut packument = None;
+    let version = match semver::Version::parse(specifier) {
+        Ok(v) => v.to_string(),
+        Err(_) => {
+            let data = npm_packument(&spec.name).await?;
+            let version = match_npm_version(&data, specifier)
+                .ok_or(FetchJSDelivrFailureError::RequestStatusCheck(404))?;
+            packument = Some(data);
+            version
+        }
+    };
+    let package_key = package_key(&spec.name, &version);
+    if let Some(v) = read_cached(&package_key, file).await {
+        return v;
+    }
+    let guard = lock_package(&package_key).await;
+    // 等待期间其他请求可能已经下载并写入了缓存
+    if let Some(v) = read_cached(&package_key, file).await {
+        return v;
+    }
+
+    let packument = match packument {
+        Some(v) => v,
+        None => npm_packument(&spec.name).await?,
+    };
+    let dist = &packument["versions"][&version]["dist"];
+    let tarball = dist["tarball"]
+        .as_str()
+        .ok_or(FetchJSDelivrFailureError::RequestStatusCheck(404))?;
+    debug!("Downloading {}@{} from {}", spec.name, version, tarball);
+    let response = authorize(build_client()?.get(tarball), tarball)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(
+            status.as_u16(),
+        ));
+    }
+    let data = download(response).await?;
+    if !verify(&data, dist["integrity"].as_str()) {
+        return Err(FetchJSDelivrFailureError::InvalidTarball(
+            "integrity mismatch".into(),
+        ));
+    }
+    let files =
+        tokio::task::spawn_blocking(move || unpack(&data, CONFIG.jsdelivr.npm_tarball_max_size))
+            .await
+            .map_err(io::Error::other)?
+            .map_err(|e| FetchJSDelivrFailureError::InvalidTarball(e.to_string()))?;
+    let base = format!("/{}@{}/", spec.id(), version);
+    let found = files
+        .iter()
+        .find(|(v, _)| v == file)
+        .map(|(v, data)| (mime::resolve(&format!("{}{}", base, v), None), data.clone()));
+    // 先返回请求的文件，包内的其余文件在后台写入缓存
+    debug!(
+        "Storing {} file(s) of {}@{} in background",
+        files.len(),
+        spec.name,
+        version
+    );
+    tokio::spawn(async move {
+        let _guard = guard;
+        store_files(package_key, base, files).await
+    });
+    found.ok_or(FetchJSDelivrFailureError::RequestStatusCheck(404))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    const MAX_SIZE: usize = 1024 * 1024;
+
+    // 构造一个 tar 条目（头部与按 512 字节对齐的内容）
+    fn entry(kind: u8, name: &str, prefix: &str, body: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", body.len()).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        let mut data = header.to_vec();
+        data.extend(body);
+        data.resize(512 + body.len().div_ceil(512) * 512, 0);
+        data
+    }
+
+    fn gzip(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        for entry in entries {
+            encoder.write_all(entry).unwrap();
+        }
+        encoder.write_all(&[0u8; 1024]).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn names(files: &[(String, Bytes)]) -> Vec<&str> {
+        files.iter().map(|(v, _)| v.as_str()).collect()
+    }
+
+    #[test]
+    fn unpacks_package_files() {
+        let tarball = gzip(&[
+            entry(b'5', "package/lib/", "", b""),
+            entry(b'0', "package/index.js", "", b"module.exports = 1;"),
+            entry(b'0', "lib/a.js", "package", &[b'a'; 600]),
+            entry(0, "package/old.js", "", b"old"),
+        ]);
+        let files = unpack(&tarball, MAX_SIZE).unwrap();
+        assert_eq!(names(&files), ["index.js", "lib/a.js", "old.js"]);
+        assert_eq!(&files[0].1[..], b"module.exports = 1;");
+        assert_eq!(&files[1].1[..], &[b'a'; 600][..]);
+        assert_eq!(&files[2].1[..], b"old");
+    }
+
+    #[test]
+    fn uses_gnu_long_names() {
+        let name = format!("package/{}/index.js", "nested".repeat(30));
+        let tarball = gzip(&[
+            entry(b'L', "././@LongLink", "", format!("{}\0", name).as_bytes()),
+            entry(b'0', &name[..100], "", b"long"),
+            entry(b'0', "package/short.js", "", b"short"),
+        ]);
+        let files = unpack(&tarball, MAX_SIZE).unwrap();
+        assert_eq!(names(&files), [&name["package/".len()..], "short.js"]);
+        assert_eq!(&files[0].1[..], b"long");
+    }
+
+    #[test]
+    fn uses_pax_paths() {
+        let path = "package/文档/说明.md";
+        let record = format!(" path={}\n", path);
+        // 记录的长度包含长度字段本身
+        let record = format!("{}{}", record.len() + 2, record);
+        let tarball = gzip(&[
+            entry(b'x', "PaxHeader/readme", "", record.as_bytes()),
+            entry(b'0', "package/readme", "", b"pax"),
+        ]);
+        let files = unpack(&tarball, MAX_SIZE).unwrap();
+        assert_eq!(names(&files), ["文档/说明.md"]);
+        assert_eq!(&files[0].1[..], b"pax");
+    }
+
+    #[test]
+    fn skips_path_traversal() {
+        let tarball = gzip(&[
+            entry(b'0', "package/../../etc/passwd", "", b"x"),
+            entry(b'0', "package/lib//a.js", "", b"x"),
+            entry(b'0', "index.js", "", b"x"),
+            entry(b'0', "package/ok.js", "", b"ok"),
+        ]);
+        let files = unpack(&tarball, MAX_SIZE).unwrap();
+        assert_eq!(names(&files), ["ok.js"]);
+    }
+
+    #[test]
+    fn rejects_truncated_tarball() {
+        let mut tar = entry(b'0', "package/index.js", "", &[b'a'; 1000]);
+        tar.truncate(512 + 100);
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&tar).unwrap();
+        let tarball = encoder.finish().unwrap();
+        let err = unpack(&tarball, MAX_SIZE).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_oversized_tarball() {
+        let tarball = gzip(&[entry(b'0', "package/index.js", "", &[b'a'; 4096])]);
+        let err = unpack(&tarball, 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
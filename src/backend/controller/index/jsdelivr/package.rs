@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+// 请求路径中的包信息，如 `npm/@hitokoto/core@1.0.0/dist/index.js`：
+// namespace = "npm"，name = "@hitokoto/core"，version = "1.0.0"，file = "dist/index.js"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+    pub namespace: String,
+    pub name: String,
+    pub version: Option<String>, // 版本、范围或标签，未指定时为 None
+    pub file: String,            // 包内的文件路径，不含开头的斜杠
+}
+
+impl PackageSpec {
+    // 只解析 npm 与 gh 路径，其余命名空间返回 None
+    pub fn parse(path: &Path) -> Option<Self> {
+        let path = path.to_str()?;
+        let (namespace, rest) = path.trim_start_matches('/').split_once('/')?;
+        // npm 作用域包与 gh 的包名均由两段组成
+        let name_segments = match namespace {
+            "npm" if rest.starts_with('@') => 2,
+            "npm" => 1,
+            "gh" => 2,
+            _ => return None,
+        };
+        let mut parts = rest.splitn(name_segments + 1, '/');
+        let mut name: Vec<&str> = (0..name_segments)
+            .map(|_| parts.next())
+            .collect::<Option<_>>()?;
+        let file = parts.next().unwrap_or("").to_string();
+        // 版本号跟在包名的最后一段之后；作用域包开头的 `@` 不是版本分隔符
+        let last = name.pop()?;
+        let offset = usize::from(name.is_empty() && last.starts_with('@'));
+        let (last, version) = match last[offset..].find('@') {
+            Some(i) => (
+                &last[..i + offset],
+                Some(last[i + offset + 1..].to_string()).filter(|v| !v.is_empty()),
+            ),
+            None => (last, None),
+        };
+        name.push(last);
+        if name.iter().any(|v| v.is_empty()) {
+            return None;
+        }
+        Some(PackageSpec {
+            namespace: namespace.to_string(),
+            name: name.join("/"),
+            version,
+            file,
+        })
+    }
+
+    // `{namespace}/{name}`，用于访问规则的匹配
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.namespace, self.name)
+    }
+
+    // 重新组合为请求路径
+    pub fn to_path(&self) -> PathBuf {
+        let mut path = self.id();
+        if let Some(v) = &self.version {
+            path = format!("{}@{}", path, v);
+        }
+        if !self.file.is_empty() {
+            path = format!("{}/{}", path, self.file);
+        }
+        PathBuf::from(path)
+    }
+}
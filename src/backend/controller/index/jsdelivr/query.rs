@@ -0,0 +1,54 @@
+use crate::CONFIG;
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+use std::convert::Infallible;
+use url::form_urlencoded;
+
+// 转发给上游的查询参数，只保留 jsdelivr.forward_query 中允许的参数，按名称和值排序并去重。
+// 其余参数（如 `?v=123` 这类缓存破坏参数）会被丢弃，不影响缓存键
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedQuery(Vec<(String, String)>);
+
+impl ForwardedQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut pairs: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .filter(|(k, _)| CONFIG.jsdelivr.forward_query.iter().any(|v| v == k))
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        ForwardedQuery(pairs)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // 规范化后的查询字符串，没有需要转发的参数时返回 None
+    pub fn to_query_string(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.0)
+                .finish(),
+        )
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ForwardedQuery {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Infallible> {
+        Outcome::Success(match request.uri().query() {
+            Some(query) if !CONFIG.jsdelivr.forward_query.is_empty() => {
+                ForwardedQuery::parse(query.as_str())
+            }
+            _ => ForwardedQuery::default(),
+        })
+    }
+}
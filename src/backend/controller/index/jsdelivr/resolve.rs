@@ -0,0 +1,216 @@
+use super::key::normalize_path;
+use super::package::PackageSpec;
+use super::types::FetchJSDelivrFailureError;
+use super::{build_client, github, npm, rules};
+use crate::cache;
+use crate::conf::resolve::ResolveSource;
+use crate::CONFIG;
+use deadpool_redis::redis::{AsyncCommands, RedisError};
+use reqwest::Url;
+use rocket::serde::json::Value;
+use semver::{Version, VersionReq};
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+// 版本未指定、为标签或范围时需要解析。确切的版本号、gh 的分支与 commit 不解析
+fn needs_resolution(spec: &PackageSpec) -> bool {
+    match spec.version.as_deref() {
+        None => true,
+        Some(v) if Version::parse(v).is_ok() => false,
+        Some(v) if spec.namespace == "gh" => v == "latest" || VersionReq::parse(v).is_ok(),
+        Some(_) => true,
+    }
+}
+
+fn resolution_key(spec: &PackageSpec, specifier: &str) -> String {
+    cache::namespaced(&format!("resolve:{}@{}", spec.id(), specifier))
+}
+
+async fn read_resolution(key: &str) -> Option<String> {
+    let mut conn = cache::try_get_connection().await?;
+    let result: Result<Option<String>, RedisError> = conn.get(key).await;
+    match result {
+        Ok(v) => {
+            cache::report_success();
+            v
+        }
+        Err(e) => {
+            cache::report_error(&e);
+            None
+        }
+    }
+}
+
+async fn write_resolution(key: &str, version: &str) {
+    if let Some(mut conn) = cache::try_get_connection().await {
+        let result: Result<(), RedisError> = conn.set_ex(key, version, CONFIG.resolve.ttl).await;
+        match result {
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+}
+
+async fn get_json(url: Url, accept: &str) -> Result<Value, FetchJSDelivrFailureError> {
+    let response = npm::authorize(build_client()?.get(url.as_str()), url.as_str())
+        .header("Accept", accept)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(FetchJSDelivrFailureError::RequestStatusCheck(
+            status.as_u16(),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+// 通过 jsDelivr 数据 API 解析，无法解析时返回 None
+async fn resolve_by_data_api(
+    spec: &PackageSpec,
+    specifier: &str,
+) -> Result<Option<String>, FetchJSDelivrFailureError> {
+    let mut url = Url::parse(&CONFIG.jsdelivr.data_api)?;
+    url.path_segments_mut()
+        .map_err(|_| FetchJSDelivrFailureError::InvalidPath("invalid data API base URL"))?
+        .pop_if_empty()
+        .extend(["v1", "packages", &spec.namespace])
+        .extend(spec.name.split('/'))
+        .push("resolved");
+    url.query_pairs_mut().append_pair("specifier", specifier);
+    let data = get_json(url, "application/json").await?;
+    Ok(data["version"].as_str().map(|v| v.to_string()))
+}
+
+// 读取 npm registry 中包的元数据（精简格式，包含各版本的 tarball 地址）
+pub(crate) async fn npm_packument(name: &str) -> Result<Value, FetchJSDelivrFailureError> {
+    let mut url = Url::parse(&CONFIG.jsdelivr.npm_registry)?;
+    url.path_segments_mut()
+        .map_err(|_| FetchJSDelivrFailureError::InvalidPath("invalid npm registry URL"))?
+        .pop_if_empty()
+        .push(name);
+    get_json(url, "application/vnd.npm.install-v1+json").await
+}
+
+// 在 npm 包的元数据中匹配版本：确切版本、dist-tags，或满足范围的最高版本
+pub(crate) fn match_npm_version(data: &Value, specifier: &str) -> Option<String> {
+    if let Ok(v) = Version::parse(specifier) {
+        return data["versions"].get(v.to_string()).map(|_| v.to_string());
+    }
+    if let Some(v) = data["dist-tags"][specifier].as_str() {
+        return Some(v.to_string());
+    }
+    let req = VersionReq::parse(specifier).ok()?;
+    data["versions"]
+        .as_object()?
+        .keys()
+        .filter_map(|v| Version::parse(v).ok())
+        .filter(|v| req.matches(v))
+        .max()
+        .map(|v| v.to_string())
+}
+
+// 通过 npm registry 解析：先匹配 dist-tags，再在所有版本中选出满足范围的最高版本
+async fn resolve_by_npm_registry(
+    spec: &PackageSpec,
+    specifier: &str,
+) -> Result<Option<String>, FetchJSDelivrFailureError> {
+    let data = npm_packument(&spec.name).await?;
+    Ok(match_npm_version(&data, specifier))
+}
+
+// 将版本范围或标签解析为确切版本，解析结果会短暂缓存。不需要解析时返回 None
+pub async fn resolve(spec: &PackageSpec) -> Result<Option<String>, FetchJSDelivrFailureError> {
+    // 开启 GitHub 回源时，gh 的分支与标签解析为 commit，缓存按 commit 区分
+    let by_github = github::is_ref(spec);
+    if !by_github && !needs_resolution(spec) {
+        return Ok(None);
+    }
+    let specifier = spec.version.as_deref().unwrap_or("latest");
+    let key = resolution_key(spec, specifier);
+    if let Some(v) = read_resolution(&key).await {
+        return Ok(Some(v));
+    }
+    let version = match (CONFIG.resolve.source, spec.namespace.as_str()) {
+        _ if by_github => match github::resolve_ref(spec).await {
+            Ok(v) => v,
+            // GitHub API 的限流（403/429）或无权访问私有仓库（404）不影响其他回源方式，
+            // 此时不解析，按请求中的分支或标签回源
+            Err(e) => {
+                warn!(
+                    "Failed to resolve {}@{} through GitHub, using the literal path: {}",
+                    spec.id(),
+                    specifier,
+                    e
+                );
+                return Ok(None);
+            }
+        },
+        (ResolveSource::Npm, "npm") => resolve_by_npm_registry(spec, specifier).await?,
+        _ => resolve_by_data_api(spec, specifier).await?,
+    };
+    match version {
+        Some(v) => {
+            debug!("Resolved {}@{} to {}", spec.id(), specifier, v);
+            write_resolution(&key, &v).await;
+            Ok(Some(v))
+        }
+        None => Err(FetchJSDelivrFailureError::RequestStatusCheck(404)),
+    }
+}
+
+pub struct ResolvedPath {
+    pub path: PathBuf,
+    pub version: Option<String>, // 包的确切版本（若已知）
+    pub resolved: bool,          // 请求中的版本是否经过解析（未指定、标签或范围）
+}
+
+impl ResolvedPath {
+    fn literal(path: PathBuf, version: Option<String>) -> Self {
+        ResolvedPath {
+            path,
+            version,
+            resolved: false,
+        }
+    }
+}
+
+// 将请求路径中的版本替换为确切版本，解析服务故障时退回到原始路径。
+// 按规范化的包名解析（共用解析缓存），替换版本时保留请求中包名的大小写
+pub async fn resolve_path(path: PathBuf) -> Result<ResolvedPath, FetchJSDelivrFailureError> {
+    if !CONFIG.resolve.enabled {
+        return Ok(ResolvedPath::literal(path, None));
+    }
+    let (mut spec, mut requested) = match (
+        PackageSpec::parse(&normalize_path(&path)),
+        PackageSpec::parse(&path),
+    ) {
+        (Some(spec), Some(requested)) => (spec, requested),
+        _ => return Ok(ResolvedPath::literal(path, None)),
+    };
+    match resolve(&spec).await {
+        Ok(Some(version)) => {
+            spec.version = Some(version.clone());
+            rules::check_version(&spec)?;
+            requested.version = Some(version.clone());
+            Ok(ResolvedPath {
+                path: requested.to_path(),
+                version: Some(version),
+                resolved: true,
+            })
+        }
+        Ok(None) => {
+            let version = spec.version.filter(|v| Version::parse(v).is_ok());
+            Ok(ResolvedPath::literal(path, version))
+        }
+        Err(e) if e.is_upstream_failure() => {
+            warn!(
+                "Failed to resolve {}, using the literal path: {}",
+                spec.id(),
+                e
+            );
+            Ok(ResolvedPath::literal(path, None))
+        }
+        Err(e) => Err(e),
+    }
+}
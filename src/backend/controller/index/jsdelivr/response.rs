@@ -0,0 +1,192 @@
+use crate::cache;
+use crate::cache::chunk::Body;
+use crate::cache::entry::{self, EntryMeta};
+use crate::utils::compress::{self, Encoding};
+use crate::utils::hash::Integrity;
+use bytes::Bytes;
+use rocket::{
+    http::{ContentType, Header, Status},
+    response::{self, Responder},
+    Request, Response,
+};
+use std::{io::Cursor, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Stale,        // 上游失败，返回已过期的缓存
+    Revalidating, // 返回已过期的缓存，同时在后台刷新
+}
+
+impl CacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Stale | CacheStatus::Revalidating => "STALE",
+        }
+    }
+}
+
+// 代理的资源
+#[derive(Debug)]
+pub struct Resource {
+    pub mime: String,
+    pub data: Body,
+    pub cache_status: CacheStatus,
+    pub age: u64,                     // 缓存已保存的时间（秒）
+    pub encoding: Option<Encoding>,   // data 使用的压缩编码
+    pub version: Option<String>,      // 包的确切版本
+    pub integrity: Option<Integrity>, // 原始内容的 SRI 摘要（若已计算）
+    pub vary_accept: bool,            // 内容按 Accept 协商（如目录列表的 HTML/JSON）
+}
+
+impl Resource {
+    pub fn new(mime: String, data: Body, cache_status: CacheStatus, age: u64) -> Self {
+        Resource {
+            mime,
+            data,
+            cache_status,
+            age,
+            encoding: None,
+            version: None,
+            integrity: None,
+            vary_accept: false,
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_integrity(mut self, integrity: Option<Integrity>) -> Self {
+        self.integrity = integrity;
+        self
+    }
+
+    pub fn with_vary_accept(mut self) -> Self {
+        self.vary_accept = true;
+        self
+    }
+}
+
+// 将回源得到的内容与预先压缩的版本一同写入缓存，返回与 Accept-Encoding 匹配的版本。
+// 压缩与 SRI 摘要（integrity 为 true 时）在同一个阻塞任务中计算；缓存不可用时只返回内容
+pub(crate) async fn store_resource(
+    key: &str,
+    mut meta: EntryMeta,
+    data: Bytes,
+    integrity: bool,
+    expire: usize,
+    encoding: Option<Encoding>,
+) -> Resource {
+    let mime = meta.mime.clone();
+    let input = data.clone();
+    let (variants, digests) = tokio::task::spawn_blocking(move || {
+        (
+            compress::variants(&mime, &input),
+            integrity.then(|| Integrity::compute(&input)),
+        )
+    })
+    .await
+    .unwrap_or_default();
+    meta.integrity = digests;
+    if let Some(mut conn) = cache::try_get_connection().await {
+        match entry::write(&mut conn, key, &meta, &data, &variants, expire).await {
+            Ok(_) => cache::report_success(),
+            Err(e) => cache::report_error(&e),
+        }
+    }
+    let resource = match variants.into_iter().find(|(v, _)| Some(*v) == encoding) {
+        Some((v, compressed)) => Resource::new(
+            meta.mime,
+            Bytes::from(compressed).into(),
+            CacheStatus::Miss,
+            0,
+        )
+        .with_encoding(Some(v)),
+        None => Resource::new(meta.mime, data.into(), CacheStatus::Miss, 0),
+    };
+    resource.with_integrity(meta.integrity)
+}
+
+impl<'r> Responder<'r, 'static> for Resource {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let content_type = ContentType::from_str(self.mime.as_str()).unwrap_or(ContentType::Binary);
+        let mut builder = Response::build();
+        builder
+            .header(content_type)
+            .header(Header::new("X-Cache", self.cache_status.as_str()));
+        match self.data {
+            Body::Full(data) => {
+                builder.sized_body(data.len(), Cursor::new(data));
+            }
+            // HEAD 请求由 Rocket 去除响应体，保留预设的大小作为 Content-Length
+            Body::SizeOnly(size) => {
+                builder.sized_body(size, Cursor::new(Vec::new()));
+            }
+            // 分块保存的资源逐块读取，不在内存中拼接完整内容
+            data => {
+                builder
+                    .raw_header("Content-Length", data.len().to_string())
+                    .streamed_body(data.into_reader());
+            }
+        }
+        if let Some(encoding) = self.encoding {
+            builder.header(Header::new("Content-Encoding", encoding.as_str()));
+        }
+        if let Some(version) = self.version {
+            builder.header(Header::new("X-Resolved-Version", version));
+        }
+        let mut vary = vec![];
+        if self.vary_accept {
+            vary.push("Accept");
+        }
+        if compress::is_compressible(&self.mime) {
+            vary.push("Accept-Encoding");
+        }
+        if !vary.is_empty() {
+            builder.header(Header::new("Vary", vary.join(", ")));
+        }
+        if self.cache_status != CacheStatus::Miss {
+            builder.header(Header::new("Age", self.age.to_string()));
+        }
+        match self.cache_status {
+            CacheStatus::Stale => {
+                builder.header(Header::new("Warning", "111 - \"Revalidation Failed\""));
+            }
+            CacheStatus::Revalidating => {
+                builder.header(Header::new("Warning", "110 - \"Response is Stale\""));
+            }
+            _ => {}
+        }
+        builder.ok()
+    }
+}
+
+// 未固定版本的请求重定向到确切版本的地址
+#[derive(Debug)]
+pub struct Redirect {
+    pub location: String,
+    pub max_age: u64,
+}
+
+impl<'r> Responder<'r, 'static> for Redirect {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .status(Status::Found)
+            .header(Header::new("Location", self.location))
+            .header(Header::new(
+                "Cache-Control",
+                format!("public, max-age={}", self.max_age),
+            ))
+            .ok()
+    }
+}
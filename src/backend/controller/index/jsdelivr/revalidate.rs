@@ -0,0 +1,59 @@
+use super::{query::ForwardedQuery, refresh_jsdelivr_resource};
+use crate::cache;
+use crate::stats::{self, STATS};
+use crate::CONFIG;
+use deadpool_redis::redis;
+use std::{collections::HashSet, path::PathBuf, sync::Mutex};
+use tracing::{debug, warn};
+
+lazy_static! {
+    // 正在后台刷新的缓存键，保证同一个键同时只有一个刷新任务
+    static ref REFRESHING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+struct RefreshGuard(String);
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        REFRESHING.lock().unwrap().remove(&self.0);
+    }
+}
+
+// 在后台从上游刷新缓存，若该键已在刷新中则直接返回
+pub fn spawn_refresh(key: String, path: PathBuf, query: ForwardedQuery) {
+    if !REFRESHING.lock().unwrap().insert(key.clone()) {
+        return;
+    }
+    let guard = RefreshGuard(key);
+    stats::incr(&STATS.background_refreshes);
+    tokio::spawn(async move {
+        let _guard = guard;
+        debug!("Refreshing {} in background", path.display());
+        if let Err(e) = refresh_jsdelivr_resource(path, &query, None).await {
+            warn!("Background refresh failed: {}", e);
+        }
+    });
+}
+
+// 记录一次临近过期时的命中，返回该键是否足够热门需要提前刷新
+pub async fn is_hot(key: &str, remaining: u64) -> bool {
+    let mut conn = match cache::try_get_connection().await {
+        Some(v) => v,
+        None => return false,
+    };
+    let hits_key = format!("{}_hits", key);
+    let result: Result<(u64,), redis::RedisError> = redis::pipe()
+        .atomic()
+        .incr(&hits_key, 1)
+        .expire(&hits_key, remaining.max(1) as usize)
+        .ignore()
+        .query_async(&mut conn)
+        .await;
+    match result {
+        Ok((hits,)) => hits >= CONFIG.cache.refresh_ahead_hits,
+        Err(e) => {
+            cache::report_error(&e);
+            false
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::combine;
+use super::key::normalize_path;
+use super::package::PackageSpec;
+use super::types::FetchJSDelivrFailureError;
+use crate::CONFIG;
+use glob::{MatchOptions, Pattern};
+use semver::{Version, VersionReq};
+use std::path::Path;
+use tracing::warn;
+
+struct CompiledVersionRule {
+    package: Pattern,
+    version: VersionReq,
+}
+
+fn compile(patterns: &[String]) -> Vec<Pattern> {
+    patterns
+        .iter()
+        .filter_map(|v| match Pattern::new(v) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                warn!("Ignoring invalid package pattern {}: {}", v, e);
+                None
+            }
+        })
+        .collect()
+}
+
+lazy_static! {
+    static ref ALLOW: Vec<Pattern> = compile(&CONFIG.access.allow);
+    static ref DENY: Vec<Pattern> = compile(&CONFIG.access.deny);
+    static ref VERSIONS: Vec<CompiledVersionRule> = CONFIG
+        .access
+        .versions
+        .iter()
+        .filter_map(|v| {
+            let package = match Pattern::new(&v.package) {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("Ignoring invalid package pattern {}: {}", v.package, e);
+                    return None;
+                }
+            };
+            let version = match VersionReq::parse(&v.version) {
+                Ok(r) => r,
+                Err(e) => {
+                    warn!("Ignoring invalid version constraint {}: {}", v.version, e);
+                    return None;
+                }
+            };
+            Some(CompiledVersionRule { package, version })
+        })
+        .collect();
+}
+
+// gh 的用户名与仓库名在规范化时转为小写，按不区分大小写匹配，
+// 使 `gh/Hitokoto-OSC/*` 这样按原样书写的规则同样生效
+fn matches(pattern: &Pattern, spec: &PackageSpec) -> bool {
+    let options = MatchOptions {
+        case_sensitive: spec.namespace != "gh",
+        ..MatchOptions::new()
+    };
+    pattern.matches_with(&spec.id(), options) || pattern.matches_with(&spec.name, options)
+}
+
+// 检查包的版本是否满足约束。只能检查确切的版本号，范围与标签在解析为确切版本后再检查
+pub fn check_version(spec: &PackageSpec) -> Result<(), FetchJSDelivrFailureError> {
+    let version = match spec.version.as_deref().map(Version::parse) {
+        Some(Ok(v)) => v,
+        _ => return Ok(()),
+    };
+    match VERSIONS
+        .iter()
+        .find(|v| matches(&v.package, spec) && !v.version.matches(&version))
+    {
+        Some(rule) => Err(FetchJSDelivrFailureError::VersionNotAllowed(
+            spec.id(),
+            version.to_string(),
+            rule.version.to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+// 按 [access] 中的规则检查请求的命名空间与包，规则匹配规范化后的路径
+pub fn check(path: &Path) -> Result<(), FetchJSDelivrFailureError> {
+    let path = &normalize_path(path);
+    let namespace = path
+        .components()
+        .next()
+        .map(|v| v.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !CONFIG.access.namespaces.is_empty() && !CONFIG.access.namespaces.contains(&namespace) {
+        return Err(FetchJSDelivrFailureError::NamespaceNotAllowed(namespace));
+    }
+    // combine 中的每个文件由 combine 模块分别检查包的规则
+    if combine::is_combine(path) {
+        return Ok(());
+    }
+    if ALLOW.is_empty() && DENY.is_empty() && VERSIONS.is_empty() {
+        return Ok(());
+    }
+    let spec = match PackageSpec::parse(path) {
+        Some(v) => v,
+        // 无法识别包名的路径（如 wp）只受命名空间限制
+        None if ALLOW.is_empty() => return Ok(()),
+        None => return Err(FetchJSDelivrFailureError::PackageNotAllowed(namespace)),
+    };
+    if DENY.iter().any(|v| matches(v, &spec))
+        || (!ALLOW.is_empty() && !ALLOW.iter().any(|v| matches(v, &spec)))
+    {
+        return Err(FetchJSDelivrFailureError::PackageNotAllowed(spec.id()));
+    }
+    check_version(&spec)
+}
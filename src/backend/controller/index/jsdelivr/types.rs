@@ -4,22 +4,107 @@ use url::ParseError;
 // impl errors
 #[derive(Error, Debug)]
 pub enum FetchJSDelivrFailureError {
-    #[error("FetchJSDelivrFailureError::PathCovert: Path is not valid UTF-8")]
+    #[error("FetchJSDelivrFailureError::Parse: {0}")]
     Parse(#[from] ParseError),
-    #[error("Failed to fetch from JSDelivr")]
+    #[error("FetchJSDelivrFailureError::PathCovert: Path is not valid UTF-8")]
     PathCovert,
+    #[error("FetchJSDelivrFailureError::PathTooLong: {0} bytes exceeds the limit")]
+    PathTooLong(usize),
+    #[error("FetchJSDelivrFailureError::InvalidPath: {0}")]
+    InvalidPath(&'static str),
+    #[error("FetchJSDelivrFailureError::UnknownNamespace: {0}")]
+    UnknownNamespace(String),
+    #[error("FetchJSDelivrFailureError::NamespaceNotAllowed: {0}")]
+    NamespaceNotAllowed(String),
+    #[error("FetchJSDelivrFailureError::PackageNotAllowed: {0}")]
+    PackageNotAllowed(String),
+    #[error("FetchJSDelivrFailureError::VersionNotAllowed: {0}@{1} does not satisfy {2}")]
+    VersionNotAllowed(String, String, String),
+    #[error("FetchJSDelivrFailureError::ArchiveTooLarge: {0} files ({1} bytes) exceeds the archive limit")]
+    ArchiveTooLarge(usize, usize),
     #[error("ReqwestOperation failed: {0}")]
     ReqwestOperation(#[from] reqwest::Error),
     #[error("RequestStatusCheck failed: {0}")]
     RequestStatusCheck(u16),
     #[error("RequestContentTypeConvert: {0}")]
     RequestContentTypeConvert(#[from] reqwest::header::ToStrError),
+    #[error("FetchJSDelivrFailureError::InvalidTarball: {0}")]
+    InvalidTarball(String),
+    #[error("FetchJSDelivrFailureError::InvalidJson: {0}")]
+    InvalidJson(#[from] rocket::serde::json::serde_json::Error),
+    #[error("CacheError::Read: {0}")]
+    CacheRead(#[from] std::io::Error),
     #[error("CacheError::Pool: {0}")]
     RedisPool(#[from] deadpool_redis::PoolError),
     #[error("CacheError::Redis: {0}")]
     Redis(#[from] deadpool_redis::redis::RedisError),
 }
 
+impl FetchJSDelivrFailureError {
+    // 返回给客户端的 HTTP 状态码
+    pub fn status(&self) -> u16 {
+        match self {
+            FetchJSDelivrFailureError::Parse(_)
+            | FetchJSDelivrFailureError::PathCovert
+            | FetchJSDelivrFailureError::InvalidPath(_) => 400,
+            FetchJSDelivrFailureError::PathTooLong(_) => 414,
+            FetchJSDelivrFailureError::UnknownNamespace(_) => 404,
+            FetchJSDelivrFailureError::NamespaceNotAllowed(_)
+            | FetchJSDelivrFailureError::PackageNotAllowed(_)
+            | FetchJSDelivrFailureError::VersionNotAllowed(..) => 403,
+            FetchJSDelivrFailureError::ArchiveTooLarge(..) => 413,
+            FetchJSDelivrFailureError::ReqwestOperation(e) if e.is_timeout() => 504,
+            FetchJSDelivrFailureError::ReqwestOperation(_) => 502,
+            FetchJSDelivrFailureError::RequestStatusCheck(status) => match status {
+                400..=499 => *status, // 上游的 4xx（如 404）原样透传
+                _ => 502,
+            },
+            FetchJSDelivrFailureError::RequestContentTypeConvert(_)
+            | FetchJSDelivrFailureError::InvalidTarball(_)
+            | FetchJSDelivrFailureError::InvalidJson(_) => 502,
+            FetchJSDelivrFailureError::CacheRead(_)
+            | FetchJSDelivrFailureError::RedisPool(_)
+            | FetchJSDelivrFailureError::Redis(_) => 503,
+        }
+    }
+
+    // 是否为上游故障（此时可以使用过期缓存兜底）。上游明确返回的 4xx 不属于故障
+    pub fn is_upstream_failure(&self) -> bool {
+        match self {
+            FetchJSDelivrFailureError::ReqwestOperation(_)
+            | FetchJSDelivrFailureError::RequestContentTypeConvert(_)
+            | FetchJSDelivrFailureError::InvalidTarball(_) => true,
+            FetchJSDelivrFailureError::RequestStatusCheck(status) => *status >= 500,
+            _ => false,
+        }
+    }
+
+    // 稳定的错误码，供客户端区分错误类型
+    pub fn code(&self) -> &'static str {
+        match self {
+            FetchJSDelivrFailureError::Parse(_)
+            | FetchJSDelivrFailureError::PathCovert
+            | FetchJSDelivrFailureError::InvalidPath(_) => "INVALID_PATH",
+            FetchJSDelivrFailureError::PathTooLong(_) => "PATH_TOO_LONG",
+            FetchJSDelivrFailureError::UnknownNamespace(_) => "UNKNOWN_NAMESPACE",
+            FetchJSDelivrFailureError::NamespaceNotAllowed(_) => "NAMESPACE_NOT_ALLOWED",
+            FetchJSDelivrFailureError::PackageNotAllowed(_) => "PACKAGE_NOT_ALLOWED",
+            FetchJSDelivrFailureError::VersionNotAllowed(..) => "VERSION_NOT_ALLOWED",
+            FetchJSDelivrFailureError::ArchiveTooLarge(..) => "ARCHIVE_TOO_LARGE",
+            FetchJSDelivrFailureError::ReqwestOperation(e) if e.is_timeout() => "UPSTREAM_TIMEOUT",
+            FetchJSDelivrFailureError::ReqwestOperation(_) => "UPSTREAM_UNREACHABLE",
+            FetchJSDelivrFailureError::RequestStatusCheck(404) => "UPSTREAM_NOT_FOUND",
+            FetchJSDelivrFailureError::RequestStatusCheck(_) => "UPSTREAM_STATUS",
+            FetchJSDelivrFailureError::RequestContentTypeConvert(_)
+            | FetchJSDelivrFailureError::InvalidTarball(_)
+            | FetchJSDelivrFailureError::InvalidJson(_) => "UPSTREAM_BAD_RESPONSE",
+            FetchJSDelivrFailureError::CacheRead(_)
+            | FetchJSDelivrFailureError::RedisPool(_)
+            | FetchJSDelivrFailureError::Redis(_) => "CACHE_UNAVAILABLE",
+        }
+    }
+}
+
 /*
 impl fmt::Display for FetchJSDelivrFailureError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
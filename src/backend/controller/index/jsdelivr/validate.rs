@@ -0,0 +1,168 @@
+use super::types::FetchJSDelivrFailureError;
+use percent_encoding::percent_decode_str;
+use std::path::{Component, Path};
+
+const MAX_PATH_LENGTH: usize = 1024;
+const MAX_SEGMENTS: usize = 64;
+const MAX_SEGMENT_LENGTH: usize = 255;
+// jsDelivr 支持的顶级命名空间
+const KNOWN_NAMESPACES: [&str; 4] = ["npm", "gh", "wp", "combine"];
+// 解码一次后仍然存在的编码（二次编码），可能被上游再次解码为路径分隔符或 `.`
+const ENCODED_TRAVERSAL: [&str; 3] = ["%2e", "%2f", "%5c"];
+
+fn invalid(reason: &'static str) -> FetchJSDelivrFailureError {
+    FetchJSDelivrFailureError::InvalidPath(reason)
+}
+
+// 检查原始（未解码）的请求路径。Rocket 会将解码后的 `..` 段直接弹出，
+// 因此编码的路径穿越只能在原始路径上发现
+pub fn validate_raw(raw: &str) -> Result<(), FetchJSDelivrFailureError> {
+    if raw.len() > MAX_PATH_LENGTH * 3 {
+        return Err(FetchJSDelivrFailureError::PathTooLong(raw.len()));
+    }
+    for segment in raw.split('/') {
+        let decoded = percent_decode_str(segment).decode_utf8_lossy();
+        if decoded == "." || decoded == ".." {
+            return Err(invalid("path traversal is not allowed"));
+        }
+        if decoded.contains('/') || decoded.contains('\\') {
+            return Err(invalid("encoded path separator is not allowed"));
+        }
+        let lowercase = decoded.to_lowercase();
+        if ENCODED_TRAVERSAL.iter().any(|v| lowercase.contains(v)) {
+            return Err(invalid("double encoded path is not allowed"));
+        }
+        if decoded.chars().any(char::is_control) {
+            return Err(invalid("control characters are not allowed"));
+        }
+    }
+    Ok(())
+}
+
+// 在回源与读取缓存之前校验请求路径，raw 为原始请求路径，path 为路由解析出的资源路径
+pub fn validate(raw: &str, path: &Path) -> Result<(), FetchJSDelivrFailureError> {
+    let len = path.as_os_str().len();
+    if len > MAX_PATH_LENGTH {
+        return Err(FetchJSDelivrFailureError::PathTooLong(len));
+    }
+    validate_raw(raw)?;
+    let mut segments = vec![];
+    for component in path.components() {
+        match component {
+            Component::Normal(v) => match v.to_str() {
+                Some(v) => segments.push(v),
+                None => return Err(FetchJSDelivrFailureError::PathCovert),
+            },
+            _ => return Err(invalid("path must be relative and normalized")),
+        }
+    }
+    if segments.len() > MAX_SEGMENTS {
+        return Err(invalid("too many path segments"));
+    }
+    if segments.iter().any(|v| v.len() > MAX_SEGMENT_LENGTH) {
+        return Err(invalid("path segment is too long"));
+    }
+    if segments.iter().any(|v| v.chars().any(char::is_control)) {
+        return Err(invalid("control characters are not allowed"));
+    }
+    match segments.first() {
+        Some(namespace) if KNOWN_NAMESPACES.contains(namespace) => Ok(()),
+        Some(namespace) => Err(FetchJSDelivrFailureError::UnknownNamespace(
+            namespace.to_string(),
+        )),
+        None => Err(invalid("path is empty")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(raw: &str) -> Result<(), FetchJSDelivrFailureError> {
+        let decoded = percent_decode_str(raw).decode_utf8_lossy();
+        validate(raw, Path::new(decoded.as_ref()))
+    }
+
+    #[test]
+    fn accepts_known_namespaces() {
+        assert!(check("npm/jquery@3.7.1/dist/jquery.min.js").is_ok());
+        assert!(check("npm/@babel/core@7/package.json").is_ok());
+        assert!(check("gh/user/repo@main/file.js").is_ok());
+        assert!(check("combine/npm/a@1/a.js,npm/b@2/b.js").is_ok());
+    }
+
+    #[test]
+    fn rejects_encoded_traversal() {
+        for raw in [
+            "npm/%2e%2e/secret",
+            "npm/%2E%2E/secret",
+            "npm/.%2e/secret",
+            "npm/a%2fb/c.js",
+            "npm/a%5cb/c.js",
+            "npm/%252e%252e/secret",
+        ] {
+            assert!(
+                matches!(
+                    validate_raw(raw),
+                    Err(FetchJSDelivrFailureError::InvalidPath(_))
+                ),
+                "{}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        assert!(matches!(
+            check("npm/jquery/a%0Ab.js"),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            validate("npm/jquery/a.js", Path::new("npm/jquery/a\u{7f}.js")),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_long_paths() {
+        let path = format!("npm/{}", "a/".repeat(MAX_PATH_LENGTH / 2));
+        assert!(path.len() > MAX_PATH_LENGTH);
+        assert!(matches!(
+            check(&path),
+            Err(FetchJSDelivrFailureError::PathTooLong(_))
+        ));
+        let raw = format!("npm/{}", "%41".repeat(MAX_PATH_LENGTH));
+        assert!(matches!(
+            validate_raw(&raw),
+            Err(FetchJSDelivrFailureError::PathTooLong(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_too_many_segments() {
+        let path = format!("npm/{}", vec!["a"; MAX_SEGMENTS].join("/"));
+        assert!(path.len() <= MAX_PATH_LENGTH);
+        assert!(matches!(
+            check(&path),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+        let path = format!("npm/{}", "a".repeat(MAX_SEGMENT_LENGTH + 1));
+        assert!(matches!(
+            check(&path),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_namespaces() {
+        assert!(matches!(
+            check("foo/jquery/a.js"),
+            Err(FetchJSDelivrFailureError::UnknownNamespace(v)) if v == "foo"
+        ));
+        assert!(matches!(
+            check(""),
+            Err(FetchJSDelivrFailureError::InvalidPath(_))
+        ));
+    }
+}
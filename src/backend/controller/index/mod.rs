@@ -4,9 +4,12 @@ use crate::utils::response::APIResponse;
 use chrono::prelude::{DateTime, Utc};
 use rocket::{
     get,
+    http::Status,
+    options,
     serde::json::{serde_json::json, Value},
     Responder,
 };
+use std::path::PathBuf;
 use timeago::Formatter;
 
 #[get("/")]
@@ -14,6 +17,12 @@ pub fn index() -> APIResponse<Value> {
     success(json!([]))
 }
 
+// CORS 预检请求，响应头由 CORS fairing 添加
+#[options("/<_path..>")]
+pub fn preflight(_path: PathBuf) -> Status {
+    Status::NoContent
+}
+
 #[derive(Responder)]
 #[response(status = 200, content_type = "image/x-icon")]
 pub struct FaviconResponser<'a>(&'a [u8]);
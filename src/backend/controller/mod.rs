@@ -1,2 +1,8 @@
+pub mod admin;
+pub mod archive;
+pub mod catcher;
+pub mod data;
+pub mod importmap;
 pub mod index;
+pub mod sri;
 pub use crate::utils;
@@ -0,0 +1,84 @@
+use super::index::jsdelivr::{
+    check_request, combine, error_response, query::ForwardedQuery, redirect_location,
+    remember_jsdelivr_resource, resolve, resource_integrity, types::FetchJSDelivrFailureError,
+};
+use crate::auth::guard::AssetAccess;
+use crate::utils::response::{success, APIResponse};
+use crate::CONFIG;
+use rocket::{
+    get,
+    http::uri::{Host, Origin},
+    serde::json::{serde_json::json, Value},
+};
+use std::path::PathBuf;
+use tracing::{error, instrument};
+
+// 可直接粘贴的 `<script>`/`<link>` 标签，其他类型的资源返回 None
+fn tag(mime: &str, url: &str, integrity: &str) -> Option<String> {
+    match mime.split(';').next().unwrap_or("").trim() {
+        "application/javascript" | "text/javascript" => Some(format!(
+            "<script src=\"{}\" integrity=\"{}\" crossorigin=\"anonymous\"></script>",
+            url, integrity
+        )),
+        "text/css" => Some(format!(
+            "<link rel=\"stylesheet\" href=\"{}\" integrity=\"{}\" crossorigin=\"anonymous\">",
+            url, integrity
+        )),
+        _ => None,
+    }
+}
+
+// 对外访问的完整地址：优先使用 server.public_url，未设置时使用请求的 Host
+pub(crate) fn public_url(host: Option<&Host<'_>>, location: String) -> String {
+    match (&CONFIG.server.public_url, host) {
+        (Some(v), _) => format!("{}{}", v.trim_end_matches('/'), location),
+        (None, Some(host)) => format!("//{}{}", host, location),
+        (None, None) => location,
+    }
+}
+
+async fn sri(
+    uri: &Origin<'_>,
+    host: Option<&Host<'_>>,
+    query: &ForwardedQuery,
+    path: PathBuf,
+) -> Result<Value, FetchJSDelivrFailureError> {
+    check_request(uri.path().as_str(), &path)?;
+    // 使用确切版本的地址，保证生成的标签指向不可变的文件
+    let resolved = resolve::resolve_path(path).await?;
+    let location = redirect_location(&resolved.path, false, query.to_query_string().as_deref());
+    let resource = if combine::is_combine(&resolved.path) {
+        combine::remember_combined(&resolved.path, None).await?
+    } else {
+        remember_jsdelivr_resource(resolved.path.clone(), query, None).await?
+    };
+    let mime = resource.mime.clone();
+    let integrity = resource_integrity(&resolved.path, query, resource).await?;
+    let url = public_url(host, location);
+    Ok(json!({
+        "url": url,
+        "mime": mime,
+        "version": resolved.version,
+        "integrity": integrity,
+        "tag": tag(&mime, &url, &integrity.sha384),
+    }))
+}
+
+// 返回资源的 SRI 摘要（sha256/sha384/sha512）与可直接使用的标签，如 /sri/npm/jquery@3/dist/jquery.min.js
+#[get("/<path..>")]
+#[instrument(skip(_access, uri, host))]
+pub async fn get(
+    _access: AssetAccess,
+    query: ForwardedQuery,
+    uri: &Origin<'_>,
+    host: Option<&Host<'_>>,
+    path: PathBuf,
+) -> APIResponse<Value> {
+    match sri(uri, host, &query, path).await {
+        Ok(v) => success(v),
+        Err(ref e) => {
+            error!("{:?}", e);
+            error_response(e)
+        }
+    }
+}
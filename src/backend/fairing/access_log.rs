@@ -0,0 +1,49 @@
+use crate::auth::{guard::resolved_key, quota};
+use crate::stats::{self, STATS};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    Request, Response,
+};
+use tracing::{info, warn};
+
+// 访问日志，同时负责累计 API Key 的流量配额
+pub struct AccessLog;
+
+#[rocket::async_trait]
+impl Fairing for AccessLog {
+    fn info(&self) -> Info {
+        Info {
+            name: "Access Log",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        stats::incr(&STATS.requests);
+        let key = resolved_key(request).await;
+        // 流式返回的响应（如分块缓存的资源）通过 Content-Length 头获取大小
+        let size = response.body().preset_size().or_else(|| {
+            response
+                .headers()
+                .get_one("Content-Length")
+                .and_then(|v| v.parse().ok())
+        });
+        info!(
+            target: "access",
+            "{} {} {} {} {}",
+            request.method(),
+            request.uri(),
+            response.status().code,
+            key.label(),
+            match size {
+                Some(v) => v.to_string(),
+                None => "-".into(),
+            }
+        );
+        if let (Some(key), Some(size)) = (key.key(), size) {
+            if let Err(e) = quota::add_bytes(key, size as u64).await {
+                warn!("Failed to record bytes for {}: {}", key.label, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::CONFIG;
+use glob::Pattern;
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::{Header, Method},
+    Request, Response,
+};
+
+// 根据 [cors] 配置为响应添加 CORS 头，预检请求由 OPTIONS 路由返回 204
+pub struct Cors;
+
+fn is_allowed(origin: &str) -> bool {
+    CONFIG.cors.allow_origins.iter().any(|v| {
+        v == "*"
+            || v.eq_ignore_ascii_case(origin)
+            || Pattern::new(v).is_ok_and(|p| p.matches(origin))
+    })
+}
+
+#[rocket::async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let origin = match request.headers().get_one("Origin") {
+            Some(v) if is_allowed(v) => v,
+            _ => return,
+        };
+        if CONFIG.cors.allow_origins.iter().any(|v| v == "*") {
+            response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
+        } else {
+            response.set_header(Header::new(
+                "Access-Control-Allow-Origin",
+                origin.to_string(),
+            ));
+            response.adjoin_header(Header::new("Vary", "Origin"));
+        }
+        let preflight = request.method() == Method::Options
+            && request.headers().contains("Access-Control-Request-Method");
+        if preflight {
+            response.set_header(Header::new(
+                "Access-Control-Allow-Methods",
+                CONFIG.cors.allow_methods.join(", "),
+            ));
+            if !CONFIG.cors.allow_headers.is_empty() {
+                response.set_header(Header::new(
+                    "Access-Control-Allow-Headers",
+                    CONFIG.cors.allow_headers.join(", "),
+                ));
+            }
+            response.set_header(Header::new(
+                "Access-Control-Max-Age",
+                CONFIG.cors.max_age.to_string(),
+            ));
+        } else if !CONFIG.cors.expose_headers.is_empty() {
+            response.set_header(Header::new(
+                "Access-Control-Expose-Headers",
+                CONFIG.cors.expose_headers.join(", "),
+            ));
+        }
+    }
+}
@@ -1,8 +1,10 @@
 mod controller;
+mod fairing;
 
-use crate::{conf::env::Environment, CONFIG};
+use crate::{auth, conf::env::Environment, CONFIG};
 use controller::*;
 use rocket::{
+    catchers,
     figment::{
         providers::{Env, Format, Toml},
         Figment, Profile,
@@ -39,21 +41,36 @@ fn config_provider() -> rocket::figment::Figment {
         ))
 }
 
-pub async fn init() -> Result<(), rocket::Error> {
-    let _rocket = rocket::custom(config_provider())
+pub async fn init() -> Result<(), Box<dyn std::error::Error>> {
+    auth::init().await?; // 加载 API Key
+    let mut rocket = rocket::custom(config_provider())
+        .attach(fairing::access_log::AccessLog)
+        .register("/", catchers![catcher::default])
         .mount(
             "/",
             routes![
                 index::index,
                 index::favicon,
                 index::about,
-                index::jsdelivr::get
+                index::jsdelivr::get,
+                index::jsdelivr::head
             ],
         )
-        .ignite()
-        .await?
-        .launch()
-        .await?;
+        .mount("/admin", routes![admin::purge, admin::warm, admin::stats])
+        .mount("/sri", routes![sri::get])
+        .mount("/importmap", routes![importmap::get, importmap::post]);
+    if CONFIG.data.enabled {
+        rocket = rocket.mount("/data", routes![data::get]);
+    }
+    if CONFIG.archive.enabled {
+        rocket = rocket.mount("/archive", routes![archive::get]);
+    }
+    if CONFIG.cors.enabled {
+        rocket = rocket
+            .attach(fairing::cors::Cors)
+            .mount("/", routes![index::preflight]);
+    }
+    let _rocket = rocket.ignite().await?.launch().await?;
 
     Ok(())
 }
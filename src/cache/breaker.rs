@@ -0,0 +1,60 @@
+use crate::utils::time::must_get_timestamp;
+use crate::CONFIG;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use tracing::{info, warn};
+
+// 缓存熔断器：连续失败达到阈值后，在冷却期内不再尝试获取 Redis 连接
+pub struct Breaker {
+    failures: AtomicU32,
+    open_until: AtomicU64, // 毫秒时间戳，0 表示未熔断
+}
+
+impl Breaker {
+    pub const fn new() -> Self {
+        Breaker {
+            failures: AtomicU32::new(0),
+            open_until: AtomicU64::new(0),
+        }
+    }
+
+    // 是否允许访问缓存。冷却期结束后只放行一个请求试探（半开），并将冷却期顺延，
+    // 试探成功则关闭熔断，失败会再次熔断；试探未报告结果时，下一个冷却期后再放行一个
+    pub fn allow(&self) -> bool {
+        let open_until = self.open_until.load(Ordering::Relaxed);
+        if open_until == 0 {
+            return true;
+        }
+        let now = must_get_timestamp() as u64;
+        if now < open_until {
+            return false;
+        }
+        let next = now + CONFIG.cache.breaker_cooldown * 1000;
+        self.open_until
+            .compare_exchange(open_until, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    // 熔断或半开状态，成功访问缓存后才关闭
+    pub fn is_open(&self) -> bool {
+        self.open_until.load(Ordering::Relaxed) != 0
+    }
+
+    pub fn success(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+        if self.open_until.swap(0, Ordering::Relaxed) != 0 {
+            info!("Cache recovered, circuit breaker closed.");
+        }
+    }
+
+    pub fn failure(&self) {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= CONFIG.cache.breaker_threshold {
+            let until = must_get_timestamp() as u64 + CONFIG.cache.breaker_cooldown * 1000;
+            self.open_until.store(until, Ordering::Relaxed);
+            warn!(
+                "Cache failed {} times in a row, circuit breaker opened for {}s.",
+                failures, CONFIG.cache.breaker_cooldown
+            );
+        }
+    }
+}
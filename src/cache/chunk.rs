@@ -0,0 +1,190 @@
+use super::codec;
+use crate::CONFIG;
+use bytes::Bytes;
+use deadpool_redis::{
+    redis::{AsyncCommands, RedisError},
+    Connection,
+};
+use futures::{stream, TryStreamExt};
+use rocket::serde::json::serde_json;
+use serde::{Deserialize, Serialize};
+use std::io;
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
+use tracing::warn;
+
+// 大对象按固定大小分块保存在 `{key}_chunk_{id}_{index}` 中，`{key}` 中只保存清单。
+// 清单以魔数开头，与 codec 编码的普通值区分
+const MANIFEST_MAGIC: &[u8; 4] = b"JDP\x02";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Manifest {
+    id: String, // 每次写入随机生成，避免并发写入时分块互相覆盖
+    chunks: usize,
+    size: usize,
+}
+
+fn chunk_key(key: &str, id: &str, index: usize) -> String {
+    format!("{}_chunk_{}_{}", key, id, index)
+}
+
+fn parse_manifest(value: &[u8]) -> Option<Manifest> {
+    value
+        .strip_prefix(MANIFEST_MAGIC)
+        .and_then(|v| serde_json::from_slice(v).ok())
+}
+
+// 缓存中读出的内容，分块保存的对象按顺序逐块读取
+#[derive(Debug)]
+pub enum Body {
+    Full(Bytes),
+    Chunked { keys: Vec<String>, size: usize },
+    SizeOnly(usize), // 只知道大小，不读取内容（如 HEAD 请求）
+}
+
+impl From<Bytes> for Body {
+    fn from(v: Bytes) -> Self {
+        Body::Full(v)
+    }
+}
+
+impl Body {
+    pub fn len(&self) -> usize {
+        match self {
+            Body::Full(v) => v.len(),
+            Body::Chunked { size, .. } | Body::SizeOnly(size) => *size,
+        }
+    }
+
+    fn stream_chunks(
+        keys: Vec<String>,
+    ) -> impl futures::Stream<Item = io::Result<Bytes>> + Send + 'static {
+        stream::try_unfold(
+            (None::<Connection>, keys.into_iter()),
+            |(conn, mut keys)| async move {
+                let key = match keys.next() {
+                    Some(v) => v,
+                    None => return Ok(None),
+                };
+                // 经过熔断器获取连接，读取结果同样计入熔断器
+                let mut conn = match conn {
+                    Some(v) => v,
+                    None => super::try_get_connection()
+                        .await
+                        .ok_or_else(|| io::Error::other("cache unavailable"))?,
+                };
+                let value: Option<Bytes> = match conn.get(&key).await {
+                    Ok(v) => {
+                        super::report_success();
+                        v
+                    }
+                    Err(e) => {
+                        super::report_error(&e);
+                        return Err(io::Error::other(e));
+                    }
+                };
+                let value = value.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("missing chunk {}", key))
+                })?;
+                Ok(Some((codec::decode(value)?, (Some(conn), keys))))
+            },
+        )
+    }
+
+    // 读取完整内容，用于需要解析内容的场景（如 JSON）
+    pub async fn into_bytes(self) -> io::Result<Bytes> {
+        match self {
+            Body::Full(v) => Ok(v),
+            Body::SizeOnly(_) => Ok(Bytes::new()),
+            Body::Chunked { keys, size } => {
+                let mut data = Vec::with_capacity(size);
+                let mut chunks = Box::pin(Body::stream_chunks(keys));
+                while let Some(chunk) = chunks.try_next().await? {
+                    data.extend_from_slice(&chunk);
+                }
+                Ok(Bytes::from(data))
+            }
+        }
+    }
+
+    pub fn into_reader(self) -> Box<dyn AsyncRead + Send + Unpin> {
+        match self {
+            Body::Full(v) => Box::new(io::Cursor::new(v)),
+            Body::SizeOnly(_) => Box::new(tokio::io::empty()),
+            Body::Chunked { keys, .. } => {
+                Box::new(StreamReader::new(Box::pin(Body::stream_chunks(keys))))
+            }
+        }
+    }
+}
+
+// 解析 `key` 中保存的值，清单会被展开为按顺序读取的分块
+pub fn parse(key: &str, value: Bytes) -> io::Result<Body> {
+    match parse_manifest(&value) {
+        Some(manifest) => Ok(Body::Chunked {
+            keys: (0..manifest.chunks)
+                .map(|i| chunk_key(key, &manifest.id, i))
+                .collect(),
+            size: manifest.size,
+        }),
+        None => codec::decode(value).map(Body::Full),
+    }
+}
+
+// 清理未被清单引用的分块，失败时只记录日志，分块最终会随过期时间被删除
+pub async fn discard(conn: &mut Connection, keys: &[String]) {
+    if keys.is_empty() {
+        return;
+    }
+    let result: Result<(), RedisError> = conn.del(keys).await;
+    if let Err(e) = result {
+        warn!("Failed to clean up partial chunks: {}", e);
+    }
+}
+
+// 准备写入 `key` 的值。超过阈值的对象会先写入所有分块，返回清单作为最终写入 `key` 的值，
+// 调用方写入清单失败时应使用 `discard` 清理返回的分块键
+pub async fn prepare(
+    conn: &mut Connection,
+    key: &str,
+    data: &[u8],
+    compressible: bool,
+    expire: usize,
+) -> Result<(Vec<u8>, Vec<String>), RedisError> {
+    let threshold = CONFIG.cache.chunk_threshold;
+    if threshold == 0 || data.len() <= threshold {
+        return Ok((codec::encode(data, compressible), vec![]));
+    }
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let mut written = vec![];
+    for (index, chunk) in data.chunks(CONFIG.cache.chunk_size.max(1)).enumerate() {
+        let chunk_key = chunk_key(key, &id, index);
+        let result: Result<(), RedisError> = conn
+            .set_ex(&chunk_key, codec::encode(chunk, compressible), expire)
+            .await;
+        if let Err(e) = result {
+            discard(conn, &written).await;
+            return Err(e);
+        }
+        written.push(chunk_key);
+    }
+    let manifest = Manifest {
+        id,
+        chunks: written.len(),
+        size: data.len(),
+    };
+    let mut value = MANIFEST_MAGIC.to_vec();
+    value.extend(serde_json::to_vec(&manifest).expect("Manifest is always serializable"));
+    Ok((value, written))
+}
+
+// 返回 `key` 的清单所引用的分块键
+pub async fn chunk_keys(conn: &mut Connection, key: &str) -> Result<Vec<String>, RedisError> {
+    let value: Option<Bytes> = conn.get(key).await?;
+    Ok(match value.as_deref().and_then(parse_manifest) {
+        Some(manifest) => (0..manifest.chunks)
+            .map(|i| chunk_key(key, &manifest.id, i))
+            .collect(),
+        None => vec![],
+    })
+}
@@ -0,0 +1,68 @@
+use crate::conf::cache::Codec;
+use crate::CONFIG;
+use bytes::Bytes;
+use std::io;
+
+// 缓存值的头部：4 字节魔数 + 1 字节压缩标记。没有该头部的值为旧版本写入的原始内容
+const MAGIC: &[u8; 4] = b"JDP\x01";
+const HEADER_LEN: usize = MAGIC.len() + 1;
+
+fn flag(codec: Codec) -> u8 {
+    match codec {
+        Codec::None => 0,
+        Codec::Zstd => 1,
+        Codec::Lz4 => 2,
+    }
+}
+
+fn from_flag(flag: u8) -> Option<Codec> {
+    match flag {
+        0 => Some(Codec::None),
+        1 => Some(Codec::Zstd),
+        2 => Some(Codec::Lz4),
+        _ => None,
+    }
+}
+
+fn with_header(codec: Codec, payload: &[u8]) -> Vec<u8> {
+    let mut value = Vec::with_capacity(HEADER_LEN + payload.len());
+    value.extend_from_slice(MAGIC);
+    value.push(flag(codec));
+    value.extend_from_slice(payload);
+    value
+}
+
+// 编码缓存值。compressible 为 false（如已经 gzip/br 压缩过的内容）或压缩后没有变小时保存原始内容
+pub fn encode(data: &[u8], compressible: bool) -> Vec<u8> {
+    let codec = CONFIG.cache.compression;
+    if !compressible || codec == Codec::None || data.len() < CONFIG.cache.compression_min_size {
+        return with_header(Codec::None, data);
+    }
+    let compressed = match codec {
+        Codec::Zstd => zstd::bulk::compress(data, CONFIG.cache.compression_level).ok(),
+        Codec::Lz4 => Some(lz4_flex::compress_prepend_size(data)),
+        Codec::None => None,
+    };
+    match compressed {
+        Some(v) if v.len() < data.len() => with_header(codec, &v),
+        _ => with_header(Codec::None, data),
+    }
+}
+
+pub fn decode(value: Bytes) -> io::Result<Bytes> {
+    if value.len() < HEADER_LEN || &value[..MAGIC.len()] != MAGIC {
+        return Ok(value); // 旧版本的原始内容
+    }
+    let payload = &value[HEADER_LEN..];
+    match from_flag(value[MAGIC.len()]) {
+        Some(Codec::None) => Ok(value.slice(HEADER_LEN..)),
+        Some(Codec::Zstd) => Ok(Bytes::from(zstd::stream::decode_all(payload)?)),
+        Some(Codec::Lz4) => lz4_flex::decompress_size_prepended(payload)
+            .map(Bytes::from)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unknown cache value codec",
+        )),
+    }
+}
@@ -0,0 +1,229 @@
+use super::chunk::{self, Body};
+use crate::utils::compress::Encoding;
+use crate::utils::hash::Integrity;
+use crate::utils::time::must_get_unix_time;
+use bytes::Bytes;
+use deadpool_redis::{
+    redis::{self, AsyncCommands, RedisError},
+    Connection,
+};
+use rocket::serde::json::serde_json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+// 缓存条目的元数据，以 JSON 形式保存在 `{key}_meta` 中，资源内容保存在 `{key}_data` 中，
+// 预压缩的版本保存在 `{key}_data_{encoding}` 中。较大的内容按 chunk 模块的格式分块保存
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EntryMeta {
+    pub mime: String,
+    pub stored_at: u64, // 写入时间（秒）
+    pub ttl: u64,       // 新鲜期（秒），超过后视为过期（stale）
+    #[serde(default)]
+    pub encodings: Vec<Encoding>, // 已保存的压缩版本
+    #[serde(default)]
+    pub size: Option<usize>, // 原始内容的大小，旧版本的元数据中没有该字段
+    #[serde(default)]
+    pub encoded_sizes: HashMap<Encoding, usize>, // 压缩版本的大小
+    #[serde(default)]
+    pub integrity: Option<Integrity>, // 原始内容的 SRI 摘要
+}
+
+impl EntryMeta {
+    pub fn new(mime: String, ttl: u64) -> Self {
+        EntryMeta {
+            mime,
+            stored_at: must_get_unix_time(),
+            ttl,
+            encodings: vec![],
+            size: None,
+            encoded_sizes: HashMap::new(),
+            integrity: None,
+        }
+    }
+
+    pub fn age(&self) -> u64 {
+        must_get_unix_time().saturating_sub(self.stored_at)
+    }
+
+    // 剩余的新鲜期（秒）
+    pub fn remaining(&self) -> u64 {
+        self.ttl.saturating_sub(self.age())
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        self.age() < self.ttl
+    }
+
+    // 返回指定压缩版本（None 为原始内容）的大小，未知时返回 None
+    pub fn size_of(&self, encoding: Option<Encoding>) -> Option<usize> {
+        match encoding {
+            Some(v) => self.encoded_sizes.get(&v).copied(),
+            None => self.size,
+        }
+    }
+}
+
+fn meta_key(key: &str) -> String {
+    format!("{}_meta", key)
+}
+
+fn data_key(key: &str, encoding: Option<Encoding>) -> String {
+    match encoding {
+        Some(v) => format!("{}_data_{}", key, v.as_str()),
+        None => format!("{}_data", key),
+    }
+}
+
+const ALL_ENCODINGS: [Option<Encoding>; 3] = [None, Some(Encoding::Gzip), Some(Encoding::Br)];
+
+// 负缓存（上游返回 404 等）单独保存，不会覆盖已有的正常缓存
+fn negative_key(key: &str) -> String {
+    format!("{}_negative", key)
+}
+
+// 只读取元数据（如 HEAD 请求），元数据损坏时视为未命中
+pub async fn read_meta(conn: &mut Connection, key: &str) -> Result<Option<EntryMeta>, RedisError> {
+    let meta: Option<String> = conn.get(meta_key(key)).await?;
+    Ok(meta.and_then(|v| serde_json::from_str::<EntryMeta>(&v).ok()))
+}
+
+// 读取缓存，优先返回指定的压缩版本，不存在时返回原始内容。
+// 元数据损坏（如格式升级）时视为未命中
+pub async fn read(
+    conn: &mut Connection,
+    key: &str,
+    encoding: Option<Encoding>,
+) -> Result<Option<(EntryMeta, Body, Option<Encoding>)>, RedisError> {
+    let meta = match read_meta(conn, key).await? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let encoding = encoding.filter(|v| meta.encodings.contains(v));
+    let data_key = data_key(key, encoding);
+    let data: Option<Bytes> = conn.get(&data_key).await?;
+    Ok(match data.map(|v| chunk::parse(&data_key, v)) {
+        Some(Ok(Body::Chunked { keys, size })) => {
+            // 分块可能已被提前淘汰，缺失时视为未命中
+            let exists: usize = conn.exists(&keys).await?;
+            if exists == keys.len() {
+                Some((meta, Body::Chunked { keys, size }, encoding))
+            } else {
+                warn!("Cache value {} has missing chunks", key);
+                None
+            }
+        }
+        Some(Ok(data)) => Some((meta, data, encoding)),
+        Some(Err(e)) => {
+            warn!("Failed to decode cache value {}: {}", key, e);
+            None
+        }
+        None => None,
+    })
+}
+
+// expire 为 Redis 中的实际保存时间，应不小于 meta.ttl。
+// 分块会先于元数据写入，元数据和清单在同一个事务中最后写入，失败时清理已写入的分块
+pub async fn write(
+    conn: &mut Connection,
+    key: &str,
+    meta: &EntryMeta,
+    data: &[u8],
+    variants: &[(Encoding, Vec<u8>)],
+    expire: usize,
+) -> Result<(), RedisError> {
+    let mut meta = meta.clone();
+    meta.encodings = variants.iter().map(|(v, _)| *v).collect();
+    meta.size = Some(data.len());
+    meta.encoded_sizes = variants.iter().map(|(v, d)| (*v, d.len())).collect();
+    let meta = serde_json::to_string(&meta).expect("EntryMeta is always serializable");
+
+    let mut values = vec![];
+    let mut chunks = vec![];
+    let mut bodies = vec![(data_key(key, None), data, true)];
+    bodies.extend(
+        variants
+            .iter()
+            .map(|(encoding, compressed)| (data_key(key, Some(*encoding)), &compressed[..], false)),
+    );
+    for (data_key, data, compressible) in bodies {
+        match chunk::prepare(conn, &data_key, data, compressible, expire).await {
+            Ok((value, written)) => {
+                values.push((data_key, value));
+                chunks.extend(written);
+            }
+            Err(e) => {
+                chunk::discard(conn, &chunks).await;
+                return Err(e);
+            }
+        }
+    }
+
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .set_ex(meta_key(key), meta, expire)
+        .ignore()
+        .del(negative_key(key))
+        .ignore();
+    for (data_key, value) in values {
+        pipe.set_ex(data_key, value, expire).ignore();
+    }
+    for encoding in [Encoding::Gzip, Encoding::Br] {
+        if !variants.iter().any(|(v, _)| *v == encoding) {
+            pipe.del(data_key(key, Some(encoding))).ignore();
+        }
+    }
+    // 旧清单引用的分块不在此处删除，正在读取的请求仍可能使用它们，分块会随过期时间被删除
+    let result: Result<(), RedisError> = pipe.query_async(conn).await;
+    if result.is_err() {
+        chunk::discard(conn, &chunks).await;
+    }
+    result
+}
+
+// 为旧版本写入的缓存补充 SRI 摘要，保留元数据剩余的保存时间。元数据不存在或已有摘要时不修改
+pub async fn write_integrity(
+    conn: &mut Connection,
+    key: &str,
+    integrity: &Integrity,
+) -> Result<(), RedisError> {
+    let mut meta = match read_meta(conn, key).await? {
+        Some(v) if v.integrity.is_none() => v,
+        _ => return Ok(()),
+    };
+    let expire: i64 = conn.pttl(meta_key(key)).await?;
+    if expire <= 0 {
+        return Ok(());
+    }
+    meta.integrity = Some(integrity.clone());
+    let meta = serde_json::to_string(&meta).expect("EntryMeta is always serializable");
+    conn.pset_ex(meta_key(key), meta, expire as usize).await
+}
+
+// 返回缓存的上游状态码
+pub async fn read_negative(conn: &mut Connection, key: &str) -> Result<Option<u16>, RedisError> {
+    conn.get(negative_key(key)).await
+}
+
+pub async fn write_negative(
+    conn: &mut Connection,
+    key: &str,
+    status: u16,
+    expire: usize,
+) -> Result<(), RedisError> {
+    conn.set_ex(negative_key(key), status, expire).await
+}
+
+pub async fn delete(conn: &mut Connection, key: &str) -> Result<usize, RedisError> {
+    let mut keys = vec![
+        meta_key(key),
+        negative_key(key),
+        format!("{}_mime", key), // 旧版本的缓存格式
+    ];
+    for encoding in ALL_ENCODINGS {
+        let data_key = data_key(key, encoding);
+        keys.extend(chunk::chunk_keys(conn, &data_key).await?);
+        keys.push(data_key);
+    }
+    conn.del(keys).await
+}
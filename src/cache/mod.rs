@@ -1,25 +1,45 @@
+pub mod breaker;
+pub mod chunk;
+pub mod codec;
+pub mod entry;
+
+use crate::stats::{self, STATS};
 use crate::CONFIG;
+use breaker::Breaker;
 use deadpool_redis::{
     redis::{AsyncCommands, FromRedisValue, RedisError, ToRedisArgs},
-    Config, Connection, CreatePoolError, Pool, PoolError, Runtime,
+    Config, Connection, CreatePoolError, Pool, PoolConfig, PoolError, Runtime, Timeouts,
 };
 use std::{
     error,
-    fmt::{self, Debug, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     future::Future,
+    time::Duration,
 };
+use tracing::warn;
 
 lazy_static! {
     static ref CACHE: Cache = Cache::init().expect("Failed to initialize cache");
 }
 
+pub static BREAKER: Breaker = Breaker::new();
+
 struct Cache {
     pool: Pool,
 }
 
 impl Cache {
     pub fn init() -> Result<Self, CreatePoolError> {
-        let cfg = Config::from_url(CONFIG.redis.to_uri());
+        let mut cfg = Config::from_url(CONFIG.redis.to_uri());
+        let timeout = Some(Duration::from_millis(CONFIG.cache.timeout));
+        cfg.pool = Some(PoolConfig {
+            timeouts: Timeouts {
+                wait: timeout,
+                create: timeout,
+                recycle: timeout,
+            },
+            ..PoolConfig::default()
+        });
         let pool = cfg.create_pool(Some(Runtime::Tokio1))?;
         Ok(Cache { pool })
     }
@@ -38,6 +58,41 @@ pub async fn get_connection() -> Result<Connection, PoolError> {
     (*CACHE).get_connection().await
 }
 
+// 为 Redis 键加上配置的前缀，避免多个部署共用同一个数据库时冲突
+pub fn namespaced(key: &str) -> String {
+    match CONFIG.cache.key_prefix.as_str() {
+        "" => key.to_string(),
+        prefix => format!("{}:{}", prefix, key),
+    }
+}
+
+// 降级模式下使用：缓存不可用时返回 None，由调用方直接回源。
+// 熔断期间不会尝试从连接池获取连接。
+pub async fn try_get_connection() -> Option<Connection> {
+    if !BREAKER.allow() {
+        stats::incr(&STATS.cache_bypassed);
+        return None;
+    }
+    match get_connection().await {
+        Ok(conn) => Some(conn),
+        Err(e) => {
+            report_error(&e);
+            None
+        }
+    }
+}
+
+// 记录一次缓存错误（软错误，不影响请求）
+pub fn report_error<E: Display>(e: &E) {
+    warn!("Cache unavailable, falling back to upstream: {}", e);
+    stats::incr(&STATS.cache_errors);
+    BREAKER.failure();
+}
+
+pub fn report_success() {
+    BREAKER.success();
+}
+
 // 回调闭包错误
 #[derive(Debug)]
 pub struct RememberFuncCallError<T: error::Error>(pub T);
@@ -0,0 +1,21 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct VersionRule {
+    pub package: String, // 包名的 glob，如 "@hitokoto/*"
+    pub version: String, // semver 约束，如 "^1.2"
+}
+
+// 包名以 `{namespace}/{name}` 的形式匹配（如 "npm/@hitokoto/core"、"gh/hitokoto-osc/hitokoto"），
+// 不带命名空间的规则（如 "@hitokoto/*"）匹配任意命名空间下的包名
+#[derive(Deserialize, Debug, Default)]
+pub struct Access {
+    #[serde(default)]
+    pub namespaces: Vec<String>, // 允许的顶级命名空间，为空时不限制
+    #[serde(default)]
+    pub allow: Vec<String>, // 允许的包，为空时不限制
+    #[serde(default)]
+    pub deny: Vec<String>, // 拒绝的包，优先于 allow
+    #[serde(default)]
+    pub versions: Vec<VersionRule>, // 包的版本约束
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Archive {
+    #[serde(default = "Archive::default_enabled")]
+    pub enabled: bool, // 是否开启 /archive 路由（打包下载包的某个版本）
+    #[serde(default = "Archive::default_max_size")]
+    pub max_size: usize, // 归档中文件的总大小上限（字节）
+    #[serde(default = "Archive::default_max_files")]
+    pub max_files: usize, // 归档中的文件数量上限，zip 格式最多 65535 个
+}
+
+impl Archive {
+    fn default_enabled() -> bool {
+        false
+    }
+
+    fn default_max_size() -> usize {
+        100 * 1024 * 1024
+    }
+
+    fn default_max_files() -> usize {
+        2000
+    }
+}
+
+impl Default for Archive {
+    fn default() -> Self {
+        Archive {
+            enabled: Archive::default_enabled(),
+            max_size: Archive::default_max_size(),
+            max_files: Archive::default_max_files(),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Purge,
+    Warm,
+    Stats,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Purge => "purge",
+            Scope::Warm => "warm",
+            Scope::Stats => "stats",
+        }
+    }
+}
+
+impl TryFrom<&str> for Scope {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "read" => Ok(Scope::Read),
+            "purge" => Ok(Scope::Purge),
+            "warm" => Ok(Scope::Warm),
+            "stats" => Ok(Scope::Stats),
+            v => Err(format!("unknown scope: {}", v)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKey {
+    pub key: String,
+    pub label: String,
+    #[serde(default)]
+    pub scopes: Vec<Scope>,
+    pub daily_requests: Option<u64>, // 每日请求数配额，None 表示不限制
+    pub daily_bytes: Option<u64>,    // 每日流量配额（字节），None 表示不限制
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Auth {
+    #[serde(default)]
+    pub protect_assets: bool, // 资源路由是否也需要 API Key（read 权限）
+    #[serde(default)]
+    pub keys: Vec<ApiKey>,
+    #[serde(default)]
+    pub use_database: bool, // 是否从 MySQL 中读取 API Key
+    #[serde(default = "Auth::default_table")]
+    pub table: String, // 不含前缀的表名，前缀取自 database.prefix
+    #[serde(default = "Auth::default_reload_interval")]
+    pub reload_interval: u64, // 秒
+}
+
+impl Auth {
+    fn default_table() -> String {
+        "api_keys".into()
+    }
+
+    fn default_reload_interval() -> u64 {
+        300
+    }
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            protect_assets: false,
+            keys: vec![],
+            use_database: false,
+            table: Auth::default_table(),
+            reload_interval: Auth::default_reload_interval(),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    None,
+    Zstd,
+    Lz4,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Cache {
+    #[serde(default = "Cache::default_key_prefix")]
+    pub key_prefix: String, // Redis 键的前缀，为空时不加前缀
+    #[serde(default = "Cache::default_key_version")]
+    pub key_version: u32, // 缓存键的版本，修改后所有旧缓存失效
+    #[serde(default = "Cache::default_ttl")]
+    pub ttl: usize, // 资源缓存时间（秒）
+    #[serde(default = "Cache::default_stale_if_error")]
+    pub stale_if_error: usize, // 过期后继续保留的时间（秒），期间上游故障时返回过期内容
+    #[serde(default = "Cache::default_stale_while_revalidate")]
+    pub stale_while_revalidate: usize, // 过期后的这段时间内（秒）直接返回过期内容并在后台刷新
+    #[serde(default)]
+    pub refresh_ahead: u64, // 距离过期不足该时间（秒）时提前刷新热门资源，0 表示关闭
+    #[serde(default = "Cache::default_refresh_ahead_hits")]
+    pub refresh_ahead_hits: u64, // 提前刷新窗口内命中达到该次数视为热门资源
+    #[serde(default = "Cache::default_negative_ttl")]
+    pub negative_ttl: usize, // 上游 404/403/410 结果的缓存时间（秒），0 表示不缓存
+    #[serde(default = "Cache::default_compression")]
+    pub compression: Codec, // 缓存值的压缩方式：zstd、lz4 或 none
+    #[serde(default = "Cache::default_compression_level")]
+    pub compression_level: i32, // zstd 压缩等级
+    #[serde(default = "Cache::default_compression_min_size")]
+    pub compression_min_size: usize, // 小于该大小（字节）的值不压缩
+    #[serde(default = "Cache::default_chunk_threshold")]
+    pub chunk_threshold: usize, // 超过该大小（字节）的资源分块保存，0 表示不分块
+    #[serde(default = "Cache::default_chunk_size")]
+    pub chunk_size: usize, // 每个分块的大小（字节）
+    #[serde(default = "Cache::default_timeout")]
+    pub timeout: u64, // 获取 Redis 连接的超时时间（毫秒）
+    #[serde(default = "Cache::default_breaker_threshold")]
+    pub breaker_threshold: u32, // 连续失败多少次后熔断
+    #[serde(default = "Cache::default_breaker_cooldown")]
+    pub breaker_cooldown: u64, // 熔断持续时间（秒），之后放行一次请求试探
+}
+
+impl Cache {
+    fn default_key_prefix() -> String {
+        "jsdelivr_proxy".into()
+    }
+
+    fn default_key_version() -> u32 {
+        1
+    }
+
+    fn default_ttl() -> usize {
+        60 * 60 * 2
+    }
+
+    fn default_stale_if_error() -> usize {
+        60 * 60 * 24
+    }
+
+    fn default_stale_while_revalidate() -> usize {
+        60 * 10
+    }
+
+    fn default_refresh_ahead_hits() -> u64 {
+        10
+    }
+
+    fn default_negative_ttl() -> usize {
+        60
+    }
+
+    fn default_compression() -> Codec {
+        Codec::Zstd
+    }
+
+    fn default_compression_level() -> i32 {
+        3
+    }
+
+    fn default_compression_min_size() -> usize {
+        256
+    }
+
+    fn default_chunk_threshold() -> usize {
+        1024 * 1024
+    }
+
+    fn default_chunk_size() -> usize {
+        512 * 1024
+    }
+
+    fn default_timeout() -> u64 {
+        500
+    }
+
+    fn default_breaker_threshold() -> u32 {
+        5
+    }
+
+    fn default_breaker_cooldown() -> u64 {
+        30
+    }
+}
+
+impl Cache {
+    // 缓存在 Redis 中的实际保存时间
+    pub fn expire(&self) -> usize {
+        self.ttl + self.stale_if_error.max(self.stale_while_revalidate)
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            key_prefix: Cache::default_key_prefix(),
+            key_version: Cache::default_key_version(),
+            ttl: Cache::default_ttl(),
+            stale_if_error: Cache::default_stale_if_error(),
+            stale_while_revalidate: Cache::default_stale_while_revalidate(),
+            refresh_ahead: 0,
+            refresh_ahead_hits: Cache::default_refresh_ahead_hits(),
+            negative_ttl: Cache::default_negative_ttl(),
+            compression: Cache::default_compression(),
+            compression_level: Cache::default_compression_level(),
+            compression_min_size: Cache::default_compression_min_size(),
+            chunk_threshold: Cache::default_chunk_threshold(),
+            chunk_size: Cache::default_chunk_size(),
+            timeout: Cache::default_timeout(),
+            breaker_threshold: Cache::default_breaker_threshold(),
+            breaker_cooldown: Cache::default_breaker_cooldown(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Compression {
+    #[serde(default = "Compression::default_enabled")]
+    pub gzip: bool,
+    #[serde(default = "Compression::default_enabled")]
+    pub brotli: bool,
+    #[serde(default = "Compression::default_gzip_level")]
+    pub gzip_level: u32, // 0 ~ 9
+    #[serde(default = "Compression::default_brotli_level")]
+    pub brotli_level: u32, // 0 ~ 11
+    #[serde(default = "Compression::default_min_size")]
+    pub min_size: usize, // 小于该大小（字节）的资源不压缩
+}
+
+impl Compression {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_gzip_level() -> u32 {
+        6
+    }
+
+    fn default_brotli_level() -> u32 {
+        9
+    }
+
+    fn default_min_size() -> usize {
+        1024
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            gzip: Compression::default_enabled(),
+            brotli: Compression::default_enabled(),
+            gzip_level: Compression::default_gzip_level(),
+            brotli_level: Compression::default_brotli_level(),
+            min_size: Compression::default_min_size(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Cors {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "Cors::default_allow_origins")]
+    pub allow_origins: Vec<String>, // 允许的来源，支持 `*` 与通配符（如 https://*.hitokoto.cn）
+    #[serde(default = "Cors::default_allow_methods")]
+    pub allow_methods: Vec<String>,
+    #[serde(default = "Cors::default_allow_headers")]
+    pub allow_headers: Vec<String>,
+    #[serde(default = "Cors::default_expose_headers")]
+    pub expose_headers: Vec<String>,
+    #[serde(default = "Cors::default_max_age")]
+    pub max_age: u64, // 预检结果的缓存时间（秒）
+}
+
+impl Cors {
+    fn default_allow_origins() -> Vec<String> {
+        vec!["*".into()]
+    }
+
+    fn default_allow_methods() -> Vec<String> {
+        vec!["GET".into(), "HEAD".into(), "OPTIONS".into()]
+    }
+
+    fn default_allow_headers() -> Vec<String> {
+        vec![
+            "Accept".into(),
+            "Authorization".into(),
+            "Content-Type".into(),
+            "X-API-Key".into(),
+        ]
+    }
+
+    fn default_expose_headers() -> Vec<String> {
+        vec![
+            "Age".into(),
+            "Content-Length".into(),
+            "X-Cache".into(),
+            "X-Resolved-Version".into(),
+        ]
+    }
+
+    fn default_max_age() -> u64 {
+        86400
+    }
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            enabled: false,
+            allow_origins: Cors::default_allow_origins(),
+            allow_methods: Cors::default_allow_methods(),
+            allow_headers: Cors::default_allow_headers(),
+            expose_headers: Cors::default_expose_headers(),
+            max_age: Cors::default_max_age(),
+        }
+    }
+}
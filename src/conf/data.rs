@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Data {
+    #[serde(default = "Data::default_enabled")]
+    pub enabled: bool, // 是否开启 /data 路由（代理 jsdelivr.data_api）
+    #[serde(default = "Data::default_ttl")]
+    pub ttl: usize, // 数据 API 响应的缓存时间（秒），过期后按 cache.stale_if_error 保留
+    #[serde(default)]
+    pub wrap: bool, // 是否将 JSON 包装为统一的响应格式，请求可以通过 `?wrap=true|false` 覆盖
+    #[serde(default = "Data::default_forward_query")]
+    pub forward_query: Vec<String>, // 转发给数据 API 并参与缓存键的查询参数，其余参数会被忽略
+}
+
+impl Data {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_ttl() -> usize {
+        60 * 10
+    }
+
+    fn default_forward_query() -> Vec<String> {
+        [
+            "by",
+            "continent",
+            "country",
+            "limit",
+            "page",
+            "period",
+            "specifier",
+            "structure",
+            "type",
+        ]
+        .iter()
+        .map(|v| v.to_string())
+        .collect()
+    }
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Data {
+            enabled: Data::default_enabled(),
+            ttl: Data::default_ttl(),
+            wrap: false,
+            forward_query: Data::default_forward_query(),
+        }
+    }
+}
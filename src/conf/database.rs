@@ -1,3 +1,4 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -30,6 +31,26 @@ impl Database {
     fn default_user() -> String {
         "root".into()
     }
+
+    // 用户名与密码中可能包含 `@`、`:`、`/` 等字符，需要编码
+    pub fn to_uri(&self) -> String {
+        format!(
+            "mysql://{}{}@{}:{}/{}",
+            utf8_percent_encode(&self.user, NON_ALPHANUMERIC),
+            if self.password.is_empty() {
+                "".into()
+            } else {
+                format!(":{}", utf8_percent_encode(&self.password, NON_ALPHANUMERIC))
+            },
+            self.host,
+            self.port,
+            self.database
+        )
+    }
+
+    pub fn table(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
 }
 
 impl Default for Database {
@@ -1,10 +1,60 @@
 use serde::Deserialize;
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Upstream {
+    Mirror, // jsdelivr.mirror 指定的 HTTP 镜像
+    Npm,    // 从 npm registry 下载 tarball（仅用于 npm 包）
+    Github, // 从 github_raw 获取仓库的文件（仅用于 gh）
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Jsdelivr {
     pub mirror: Option<String>,
     pub user_agent: Option<String>,
     pub referer: Option<String>,
+    #[serde(default = "Jsdelivr::default_data_api")]
+    pub data_api: String, // jsDelivr 数据 API，用于解析版本
+    #[serde(default = "Jsdelivr::default_npm_registry")]
+    pub npm_registry: String,
+    pub npm_token: Option<String>, // 访问 npm_registry 时使用的 Bearer Token（如私有的 Verdaccio）
+    #[serde(default = "Jsdelivr::default_npm_tarball_max_size")]
+    pub npm_tarball_max_size: usize, // 解压后的 tarball 大小上限（字节），同时限制下载的大小
+    #[serde(default = "Jsdelivr::default_github_raw")]
+    pub github_raw: String, // 文件地址模板，支持 {owner}、{repo}、{ref}、{path}
+    #[serde(default = "Jsdelivr::default_github_api")]
+    pub github_api: String, // GitHub 兼容的 API，用于将分支解析为 commit
+    pub github_token: Option<String>, // 访问私有仓库的 Token
+    #[serde(default = "Jsdelivr::default_upstreams")]
+    pub upstreams: Vec<Upstream>, // 按顺序尝试的回源方式，前一个故障时使用下一个
+    #[serde(default)]
+    pub forward_query: Vec<String>, // 转发给上游并参与缓存键的查询参数，其余参数会被忽略
+}
+
+impl Jsdelivr {
+    fn default_data_api() -> String {
+        "https://data.jsdelivr.com".into()
+    }
+
+    fn default_npm_registry() -> String {
+        "https://registry.npmjs.org".into()
+    }
+
+    fn default_npm_tarball_max_size() -> usize {
+        200 * 1024 * 1024
+    }
+
+    fn default_github_raw() -> String {
+        "https://raw.githubusercontent.com/{owner}/{repo}/{ref}/{path}".into()
+    }
+
+    fn default_github_api() -> String {
+        "https://api.github.com".into()
+    }
+
+    fn default_upstreams() -> Vec<Upstream> {
+        vec![Upstream::Mirror]
+    }
 }
 
 impl Default for Jsdelivr {
@@ -13,6 +63,15 @@ impl Default for Jsdelivr {
             mirror: Some("https://cdn.jsdelivr.net".into()),
             user_agent: None,
             referer: None,
+            data_api: Jsdelivr::default_data_api(),
+            npm_registry: Jsdelivr::default_npm_registry(),
+            npm_token: None,
+            npm_tarball_max_size: Jsdelivr::default_npm_tarball_max_size(),
+            github_raw: Jsdelivr::default_github_raw(),
+            github_api: Jsdelivr::default_github_api(),
+            github_token: None,
+            upstreams: Jsdelivr::default_upstreams(),
+            forward_query: vec![],
         }
     }
 }
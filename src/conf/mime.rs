@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MimeOverride {
+    pub extension: Option<String>, // 按扩展名匹配，如 "ts"
+    pub pattern: Option<String>,   // 按路径匹配的 glob，如 "/npm/foo@*/dist/*.js"
+    pub mime: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Mime {
+    #[serde(default = "Mime::default_fallback")]
+    pub fallback: String, // 上游未返回且扩展名未知时使用
+    #[serde(default)]
+    pub extensions: HashMap<String, String>, // 扩展内置的扩展名表
+    #[serde(default)]
+    pub overrides: Vec<MimeOverride>, // 强制指定的 MIME，优先于上游返回的 Content-Type
+}
+
+impl Mime {
+    fn default_fallback() -> String {
+        "application/octet-stream".into()
+    }
+}
+
+impl Default for Mime {
+    fn default() -> Self {
+        Mime {
+            fallback: Mime::default_fallback(),
+            extensions: HashMap::new(),
+            overrides: vec![],
+        }
+    }
+}
@@ -3,30 +3,66 @@ use std::error::Error;
 use config::{Config as conf, Environment as Env, File};
 use serde::Deserialize;
 
+pub mod access;
+pub mod archive;
+pub mod auth;
+pub mod cache;
+pub mod compression;
+pub mod cors;
+pub mod data;
 pub mod database;
 pub mod env;
 pub mod jsdelivr;
+pub mod mime;
 pub mod rabbitmq;
 pub mod redis;
+pub mod resolve;
 pub mod server;
 use self::redis::Redis;
+use access::Access;
+use archive::Archive;
+use auth::Auth;
+use cache::Cache;
+use compression::Compression;
+use cors::Cors;
+use data::Data;
 use database::Database;
 use env::Environment;
 use jsdelivr::Jsdelivr;
+use mime::Mime;
 use rabbitmq::RabbitMQ;
+use resolve::Resolve;
 
 #[derive(Deserialize)]
 pub struct Config {
     pub env: Environment,
     #[serde(default)]
+    pub access: Access,
+    #[serde(default)]
+    pub archive: Archive,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub cache: Cache,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub cors: Cors,
+    #[serde(default)]
+    pub data: Data,
+    #[serde(default)]
     pub database: Database,
     #[serde(default)]
     pub jsdelivr: Jsdelivr,
     #[serde(default)]
+    pub mime: Mime,
+    #[serde(default)]
     pub redis: Redis,
     #[serde(default)]
     pub rabbitmq: RabbitMQ,
     #[serde(default)]
+    pub resolve: Resolve,
+    #[serde(default)]
     pub server: server::Server,
 }
 
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolveSource {
+    Jsdelivr, // jsDelivr 数据 API
+    Npm,      // npm registry（仅用于 npm 包，gh 仍使用 jsDelivr 数据 API）
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Resolve {
+    #[serde(default = "Resolve::default_enabled")]
+    pub enabled: bool, // 将版本范围与标签解析为确切版本后再缓存
+    #[serde(default = "Resolve::default_source")]
+    pub source: ResolveSource,
+    #[serde(default = "Resolve::default_ttl")]
+    pub ttl: usize, // 解析结果的缓存时间（秒）
+    #[serde(default)]
+    pub redirect: Vec<String>, // 这些命名空间下未固定版本的请求以 302 重定向到确切版本的地址
+    #[serde(default = "Resolve::default_redirect_max_age")]
+    pub redirect_max_age: u64, // 重定向响应的 Cache-Control max-age（秒）
+}
+
+impl Resolve {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_source() -> ResolveSource {
+        ResolveSource::Jsdelivr
+    }
+
+    fn default_ttl() -> usize {
+        300
+    }
+
+    fn default_redirect_max_age() -> u64 {
+        60
+    }
+}
+
+impl Default for Resolve {
+    fn default() -> Self {
+        Resolve {
+            enabled: Resolve::default_enabled(),
+            source: Resolve::default_source(),
+            ttl: Resolve::default_ttl(),
+            redirect: vec![],
+            redirect_max_age: Resolve::default_redirect_max_age(),
+        }
+    }
+}
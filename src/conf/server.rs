@@ -4,6 +4,7 @@ use serde::Deserialize;
 pub struct Server {
     pub host: Option<String>,
     pub port: Option<u16>,
+    pub public_url: Option<String>, // 对外访问的地址，用于生成 SRI 标签等，未设置时使用请求的 Host
 }
 
 impl Default for Server {
@@ -11,6 +12,7 @@ impl Default for Server {
         Server {
             host: Some("0.0.0.0".to_string()),
             port: Some(28319),
+            public_url: None,
         }
     }
 }
\ No newline at end of file
@@ -2,11 +2,13 @@ use std::error::Error;
 
 use colored::*;
 use tracing::{info, warn};
+mod auth;
 mod backend;
 mod cache;
 mod command;
 pub mod conf;
 mod logger;
+mod stats;
 pub mod utils;
 
 #[macro_use]
@@ -0,0 +1,41 @@
+use rocket::serde::json::{serde_json::json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// 进程内的运行统计，重启后清零
+#[derive(Default)]
+pub struct Stats {
+    pub requests: AtomicU64,
+    pub cache_hits: AtomicU64,
+    pub cache_misses: AtomicU64,
+    pub negative_hits: AtomicU64,
+    pub upstream_fetches: AtomicU64,
+    pub upstream_errors: AtomicU64,
+    pub stale_served: AtomicU64,
+    pub background_refreshes: AtomicU64,
+    pub cache_errors: AtomicU64,
+    pub cache_bypassed: AtomicU64, // 熔断期间跳过缓存的次数
+}
+
+lazy_static! {
+    pub static ref STATS: Stats = Stats::default();
+}
+
+pub fn incr(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn snapshot() -> Value {
+    json!({
+        "requests": STATS.requests.load(Ordering::Relaxed),
+        "cache_hits": STATS.cache_hits.load(Ordering::Relaxed),
+        "cache_misses": STATS.cache_misses.load(Ordering::Relaxed),
+        "negative_hits": STATS.negative_hits.load(Ordering::Relaxed),
+        "upstream_fetches": STATS.upstream_fetches.load(Ordering::Relaxed),
+        "upstream_errors": STATS.upstream_errors.load(Ordering::Relaxed),
+        "stale_served": STATS.stale_served.load(Ordering::Relaxed),
+        "background_refreshes": STATS.background_refreshes.load(Ordering::Relaxed),
+        "cache_errors": STATS.cache_errors.load(Ordering::Relaxed),
+        "cache_bypassed": STATS.cache_bypassed.load(Ordering::Relaxed),
+        "cache_breaker_open": crate::cache::BREAKER.is_open(),
+    })
+}
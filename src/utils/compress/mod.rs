@@ -0,0 +1,134 @@
+use crate::utils::mime;
+use crate::CONFIG;
+use flate2::{write::GzEncoder, Compression};
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, io::Write};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Gzip,
+    Br,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Br => "br",
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        match self {
+            Encoding::Gzip => CONFIG.compression.gzip,
+            Encoding::Br => CONFIG.compression.brotli,
+        }
+    }
+}
+
+// 服务端的偏好顺序
+const PREFERENCE: [Encoding; 2] = [Encoding::Br, Encoding::Gzip];
+
+// 根据 Accept-Encoding 选出的编码，None 表示不压缩
+pub struct AcceptEncoding(pub Option<Encoding>);
+
+impl AcceptEncoding {
+    pub fn parse(header: &str) -> Option<Encoding> {
+        let accepted: Vec<&str> = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let name = parts.next()?;
+                let rejected = parts.any(|p| {
+                    p.strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q <= 0.0)
+                });
+                if rejected {
+                    None
+                } else {
+                    Some(name)
+                }
+            })
+            .collect();
+        PREFERENCE
+            .iter()
+            .find(|v| v.enabled() && accepted.iter().any(|a| a.eq_ignore_ascii_case(v.as_str())))
+            .copied()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptEncoding {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Infallible> {
+        Outcome::Success(AcceptEncoding(
+            request
+                .headers()
+                .get_one("Accept-Encoding")
+                .and_then(AcceptEncoding::parse),
+        ))
+    }
+}
+
+pub fn is_compressible(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or("").trim();
+    mime::is_text(mime)
+        || matches!(
+            essence,
+            "application/wasm"
+                | "image/svg+xml"
+                | "image/x-icon"
+                | "font/ttf"
+                | "font/otf"
+                | "application/vnd.ms-fontobject"
+        )
+}
+
+pub fn compress(data: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(
+                Vec::with_capacity(data.len() / 2),
+                Compression::new(CONFIG.compression.gzip_level),
+            );
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Encoding::Br => {
+            let mut output = Vec::with_capacity(data.len() / 2);
+            {
+                let mut encoder = brotli::CompressorWriter::new(
+                    &mut output,
+                    4096,
+                    CONFIG.compression.brotli_level,
+                    22,
+                );
+                encoder.write_all(data)?;
+            }
+            Ok(output)
+        }
+    }
+}
+
+// 计算所有启用的压缩版本，压缩后没有变小的版本会被丢弃。
+// 压缩会阻塞，应在 spawn_blocking 中调用
+pub fn variants(mime: &str, data: &[u8]) -> Vec<(Encoding, Vec<u8>)> {
+    if data.len() < CONFIG.compression.min_size || !is_compressible(mime) {
+        return vec![];
+    }
+    PREFERENCE
+        .iter()
+        .filter(|v| v.enabled())
+        .filter_map(|v| match compress(data, *v) {
+            Ok(compressed) if compressed.len() < data.len() => Some((*v, compressed)),
+            _ => None,
+        })
+        .collect()
+}
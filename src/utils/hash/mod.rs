@@ -1 +1,25 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
 
+// 子资源完整性（SRI）摘要，格式为 `{algorithm}-{base64}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Integrity {
+    pub sha256: String,
+    pub sha384: String,
+    pub sha512: String,
+}
+
+fn digest<D: Digest>(name: &str, data: &[u8]) -> String {
+    format!("{}-{}", name, STANDARD.encode(D::digest(data)))
+}
+
+impl Integrity {
+    pub fn compute(data: &[u8]) -> Self {
+        Integrity {
+            sha256: digest::<Sha256>("sha256", data),
+            sha384: digest::<Sha384>("sha384", data),
+            sha512: digest::<Sha512>("sha512", data),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::CONFIG;
+use glob::Pattern;
+use phf::phf_map;
+use rocket::http::ContentType;
+use std::{path::Path, str::FromStr};
+use tracing::warn;
+
+static EXTENSION_MAP: phf::Map<&'static str, &'static str> = phf_map! {
+    "js" => "application/javascript",
+    "mjs" => "application/javascript",
+    "cjs" => "application/javascript",
+    "jsx" => "text/jsx",
+    "ts" => "text/plain",
+    "tsx" => "text/plain",
+    "json" => "application/json",
+    "map" => "application/json",
+    "jsonld" => "application/ld+json",
+    "webmanifest" => "application/manifest+json",
+    "wasm" => "application/wasm",
+    "css" => "text/css",
+    "scss" => "text/x-scss",
+    "less" => "text/x-less",
+    "html" => "text/html",
+    "htm" => "text/html",
+    "xml" => "application/xml",
+    "txt" => "text/plain",
+    "md" => "text/markdown",
+    "csv" => "text/csv",
+    "yml" => "text/yaml",
+    "yaml" => "text/yaml",
+    "svg" => "image/svg+xml",
+    "png" => "image/png",
+    "jpg" => "image/jpeg",
+    "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "avif" => "image/avif",
+    "ico" => "image/x-icon",
+    "bmp" => "image/bmp",
+    "woff" => "font/woff",
+    "woff2" => "font/woff2",
+    "ttf" => "font/ttf",
+    "otf" => "font/otf",
+    "eot" => "application/vnd.ms-fontobject",
+    "mp3" => "audio/mpeg",
+    "ogg" => "audio/ogg",
+    "wav" => "audio/wav",
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    "pdf" => "application/pdf",
+    "zip" => "application/zip",
+    "gz" => "application/gzip",
+    "tgz" => "application/gzip",
+};
+
+// 上游返回这些类型时视为未知，继续按扩展名推断
+const GENERIC_MIME: [&str; 2] = ["application/octet-stream", "binary/octet-stream"];
+
+struct CompiledOverride {
+    extension: Option<String>,
+    pattern: Option<Pattern>,
+    mime: String,
+}
+
+lazy_static! {
+    static ref OVERRIDES: Vec<CompiledOverride> = CONFIG
+        .mime
+        .overrides
+        .iter()
+        .filter_map(|v| {
+            let pattern = match &v.pattern {
+                Some(p) => match Pattern::new(p) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        warn!("Ignoring invalid MIME override pattern {}: {}", p, e);
+                        return None;
+                    }
+                },
+                None => None,
+            };
+            Some(CompiledOverride {
+                extension: v
+                    .extension
+                    .as_ref()
+                    .map(|e| e.trim_start_matches('.').to_lowercase()),
+                pattern,
+                mime: v.mime.clone(),
+            })
+        })
+        .collect();
+}
+
+fn extension_of(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|v| v.to_str())
+        .map(|v| v.to_lowercase())
+}
+
+pub fn from_extension(path: &str) -> Option<String> {
+    let ext = extension_of(path)?;
+    if let Some(v) = CONFIG.mime.extensions.get(&ext) {
+        return Some(v.clone());
+    }
+    EXTENSION_MAP.get(ext.as_str()).map(|v| v.to_string())
+}
+
+fn find_override(path: &str) -> Option<String> {
+    let ext = extension_of(path);
+    OVERRIDES
+        .iter()
+        .find(|v| {
+            let extension_matched = match (&v.extension, &ext) {
+                (Some(expected), Some(ext)) => expected == ext,
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+            let pattern_matched = match &v.pattern {
+                Some(p) => p.matches(path),
+                None => true,
+            };
+            (v.extension.is_some() || v.pattern.is_some()) && extension_matched && pattern_matched
+        })
+        .map(|v| v.mime.clone())
+}
+
+pub fn is_text(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or("").trim();
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence,
+            "application/javascript" | "application/json" | "application/xml"
+        )
+}
+
+// 为文本类型补充 charset=utf-8
+fn with_charset(mime: String) -> String {
+    if is_text(&mime) && !mime.to_lowercase().contains("charset=") {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime
+    }
+}
+
+// 解析资源的 MIME：强制覆盖 > 上游 Content-Type > 扩展名 > 默认值
+pub fn resolve(path: &str, upstream: Option<&str>) -> String {
+    if let Some(v) = find_override(path) {
+        return with_charset(v);
+    }
+    let upstream = upstream
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .filter(|v| ContentType::from_str(v).is_ok())
+        .filter(|v| !GENERIC_MIME.iter().any(|g| v.to_lowercase().starts_with(g)));
+    let mime = match upstream {
+        Some(v) => v.to_string(),
+        None => from_extension(path).unwrap_or_else(|| CONFIG.mime.fallback.clone()),
+    };
+    with_charset(mime)
+}
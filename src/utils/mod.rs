@@ -1,3 +1,5 @@
+pub mod compress;
 pub mod hash;
+pub mod mime;
 pub mod response;
 pub mod time;
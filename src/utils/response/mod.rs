@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 pub struct ResponseBase<T> {
     pub status: i64,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>, // 机器可读的错误码
     pub data: Option<T>,
     pub ts: u128,
 }
@@ -18,7 +20,13 @@ static ERROR_MESSAGE_MAP: phf::Map<&'static str, &'static str> = phf_map! {
     "401" => "Unauthorized",
     "403" => "Forbidden",
     "404" => "Not Found",
+    "413" => "Content Too Large",
+    "414" => "URI Too Long",
+    "429" => "Too Many Requests",
     "500" => "Server Error",
+    "502" => "Bad Gateway",
+    "503" => "Service Unavailable",
+    "504" => "Gateway Timeout",
 };
 
 pub fn success<T>(data: T) -> Custom<Json<ResponseBase<T>>> {
@@ -31,6 +39,7 @@ pub fn success_with_message<T>(data: T, message: String) -> Custom<Json<Response
         Json(ResponseBase {
             status: 200,
             message,
+            code: None,
             data: Some(data),
             ts: must_get_timestamp(),
         }),
@@ -45,6 +54,15 @@ pub fn fail_with_message<T>(
     code: i64,
     data: Option<T>,
     message: String,
+) -> Custom<Json<ResponseBase<T>>> {
+    fail_with_code(code, None, data, message)
+}
+
+pub fn fail_with_code<T>(
+    code: i64,
+    error_code: Option<&str>,
+    data: Option<T>,
+    message: String,
 ) -> Custom<Json<ResponseBase<T>>> {
     Custom(
         if code > 0 {
@@ -62,6 +80,7 @@ pub fn fail_with_message<T>(
             } else {
                 message
             },
+            code: error_code.map(String::from),
             data,
             ts: must_get_timestamp(),
         }),
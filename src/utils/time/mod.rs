@@ -9,3 +9,8 @@ pub fn get_timestamp() -> Result<u128, SystemTimeError> {
 pub fn must_get_timestamp() -> u128 {
     get_timestamp().unwrap()
 }
+
+// 秒级 Unix 时间戳
+pub fn must_get_unix_time() -> u64 {
+    (must_get_timestamp() / 1000) as u64
+}